@@ -1,9 +1,301 @@
+use crate::audio::backend::{AudioBackend, DeviceBackend, OfflineBackend};
+use crate::audio::bus::BusId;
+use crate::audio::effects::Effect;
+use crate::audio::mixer::{Mixer, SoundBuffer, VoiceHandle, VoiceSettings, VoiceStealing};
+use crate::audio::music::{MusicStream, MusicTrack};
+use crate::audio::spatial::{Emitter, SpatialSettings, SpatialVoice};
+use crate::audio::mixer::VoiceId;
+use crate::audio::synth::SynthParams;
+use crate::video::sprite::SpriteId;
+use crate::utility::file_parser;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub enum AudioError {
+    IOError(std::io::Error),
+    InvalidFormat(String),
+    UnsupportedFormat(String),
+    InvalidSound(String),
+    BackendError(String),
+}
+
+impl std::fmt::Display for AudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioError::IOError(e)           => write!(f, "IOError: {}", e),
+            AudioError::InvalidFormat(e)     => write!(f, "InvalidFormat: {}", e),
+            AudioError::UnsupportedFormat(e) => write!(f, "UnsupportedFormat: {}", e),
+            AudioError::InvalidSound(e)      => write!(f, "InvalidSound: {}", e),
+            AudioError::BackendError(e)      => write!(f, "BackendError: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AudioError {
+    fn from(value: std::io::Error) -> Self {
+        AudioError::IOError(value)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct SoundId {
+    id: u32,
+}
+
 pub struct AudioManager {
+    backend: Box<dyn AudioBackend>,
+    mixer: Arc<Mutex<Mixer>>,
+    sounds: HashMap<SoundId, Arc<SoundBuffer>>,
+    last_sound_id: u32,
+    listener_position: (f32, f32),
+    listener_sprite: Option<SpriteId>,
+    spatial_settings: SpatialSettings,
+    spatial_voices: Vec<SpatialVoice>,
+}
 
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AudioManager {
+    /// Opens the default output device, falling back to a silent `OfflineBackend` if that fails.
     pub fn new() -> Self {
-        AudioManager{}
+        match DeviceBackend::new() {
+            Ok(backend) => Self::with_backend(Box::new(backend)),
+            Err(err) => {
+                eprintln!("Error: Failed to open audio device, audio will be silent:\n\t{}", err);
+                Self::with_backend(Box::new(OfflineBackend::default()))
+            }
+        }
+    }
+
+    pub fn with_backend(mut backend: Box<dyn AudioBackend>) -> Self {
+        let mixer = Arc::new(Mutex::new(Mixer::new(backend.sample_rate())));
+
+        if let Err(err) = backend.start(mixer.clone()) {
+            eprintln!("Error: Failed to start audio backend, audio will be silent:\n\t{}", err);
+        }
+
+        Self {
+            backend,
+            mixer,
+            sounds: HashMap::new(),
+            last_sound_id: 0,
+            listener_position: (0.0, 0.0),
+            listener_sprite: None,
+            spatial_settings: SpatialSettings::default(),
+            spatial_voices: Vec::new(),
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.backend.sample_rate()
+    }
+
+    /// Decodes a WAV file fully into memory.
+    pub fn load_sound(&mut self, path: &str) -> Result<SoundId, AudioError> {
+        let (sample_rate, channels, samples) = file_parser::get_samples_from_wav(path)?;
+        if channels > 2 {
+            return Err(AudioError::UnsupportedFormat(
+                format!("{} has {} channels, only mono and stereo are supported", path, channels)
+            ));
+        }
+        Ok(self.add_sound(SoundBuffer::new(samples, channels, sample_rate)))
+    }
+
+    pub fn add_sound(&mut self, sound: SoundBuffer) -> SoundId {
+        let sound_id = SoundId { id: self.last_sound_id };
+        self.last_sound_id += 1;
+        self.sounds.insert(sound_id, Arc::new(sound));
+        sound_id
+    }
+
+    /// Generates a sound effect from synthesizer parameters
+    pub fn synthesize_sound(&mut self, params: &SynthParams) -> SoundId {
+        self.add_sound(params.generate())
+    }
+
+    pub fn save_sound_wav(&self, sound_id: SoundId, path: &str) -> Result<(), AudioError> {
+        self.sounds.get(&sound_id)
+            .ok_or(AudioError::InvalidSound(format!("No sound loaded with id {:?}", sound_id)))?
+            .save_wav(path)
+    }
+
+    pub fn remove_sound(&mut self, sound_id: SoundId) {
+        self.sounds.remove(&sound_id);
+    }
+
+    pub fn get_sound(&self, sound_id: SoundId) -> Option<&SoundBuffer> {
+        self.sounds.get(&sound_id).map(|sound| sound.as_ref())
+    }
+
+    pub fn play_sound(&mut self, sound_id: SoundId) -> Result<VoiceHandle, AudioError> {
+        self.play_sound_with(sound_id, VoiceSettings::default())
+    }
+
+    pub fn play_sound_with(&mut self, sound_id: SoundId, settings: VoiceSettings) -> Result<VoiceHandle, AudioError> {
+        let sound = self.sounds.get(&sound_id)
+            .ok_or(AudioError::InvalidSound(format!("No sound loaded with id {:?}", sound_id)))?;
+        let voice_id = self.mixer.lock().unwrap().play(sound.clone(), settings);
+        Ok(VoiceHandle::new(voice_id, self.mixer.clone()))
+    }
+
+    /// Plays a sound whose volume and pan depend on its distance from the listener. The
+    /// `volume` in `settings` is the volume heard when the emitter is right on the listener.
+    pub fn play_spatial(
+        &mut self,
+        sound_id: SoundId,
+        emitter: Emitter,
+        settings: VoiceSettings,
+        spatial: SpatialSettings,
+    ) -> Result<VoiceHandle, AudioError> {
+        // start silent, the first update sets the real volume
+        let handle = self.play_sound_with(sound_id, VoiceSettings { volume: 0.0, ..settings })?;
+        let mut voice = SpatialVoice::new(handle.clone(), emitter, settings.volume, spatial);
+        voice.update(self.listener_position, &|_| None);
+        self.spatial_voices.push(voice);
+        Ok(handle)
+    }
+
+    pub fn play_sound_at(&mut self, sound_id: SoundId, x: f32, y: f32) -> Result<VoiceHandle, AudioError> {
+        self.play_spatial(sound_id, Emitter::Point(x, y), VoiceSettings::default(), self.spatial_settings)
+    }
+
+    /// Plays a sound that follows a sprite around
+    pub fn play_sound_attached(&mut self, sound_id: SoundId, sprite: SpriteId) -> Result<VoiceHandle, AudioError> {
+        self.play_spatial(sound_id, Emitter::Sprite(sprite), VoiceSettings::default(), self.spatial_settings)
+    }
+
+    /// Moves the emitter of a positional sound, takes effect on the next `update_spatial`
+    pub fn set_emitter(&mut self, voice: VoiceId, emitter: Emitter) {
+        if let Some(voice) = self.spatial_voices.iter_mut().find(|spatial| spatial.id() == voice) {
+            voice.set_emitter(emitter);
+        }
+    }
+
+    /// Settings used by `play_sound_at` and `play_sound_attached`
+    pub fn set_spatial_settings(&mut self, settings: SpatialSettings) {
+        self.spatial_settings = settings;
+    }
+
+    pub fn get_spatial_settings(&self) -> SpatialSettings {
+        self.spatial_settings
+    }
+
+    pub fn set_listener_position(&mut self, x: f32, y: f32) {
+        self.listener_sprite = None;
+        self.listener_position = (x, y);
+    }
+
+    /// Makes the listener follow a sprite, usually the player
+    pub fn attach_listener(&mut self, sprite: Option<SpriteId>) {
+        self.listener_sprite = sprite;
+    }
+
+    pub fn get_listener_position(&self) -> (f32, f32) {
+        self.listener_position
+    }
+
+    /// Recomputes the volume and pan of every positional sound. `sprite_position` returns the
+    /// center of a sprite, or None if it no longer exists.
+    pub fn update_spatial<F>(&mut self, sprite_position: F)
+    where
+        F: Fn(SpriteId) -> Option<(f32, f32)>,
+    {
+        if let Some(position) = self.listener_sprite.and_then(&sprite_position) {
+            self.listener_position = position;
+        }
+
+        self.spatial_voices.retain(|voice| voice.is_playing());
+        for voice in self.spatial_voices.iter_mut() {
+            voice.update(self.listener_position, &sprite_position);
+        }
+    }
+
+    pub fn set_voice_limit(&mut self, limit: usize, stealing: VoiceStealing) {
+        self.mixer.lock().unwrap().set_voice_limit(limit, stealing);
+    }
+
+    pub fn active_voices(&self) -> usize {
+        self.mixer.lock().unwrap().active_voices()
+    }
+
+    pub fn stop_all(&mut self) {
+        self.mixer.lock().unwrap().stop_all();
+    }
+
+    /// Streams a music track, replacing whatever music is currently playing.
+    pub fn play_music(&mut self, track: &MusicTrack) -> Result<(), AudioError> {
+        let stream = MusicStream::open(track)?;
+        self.mixer.lock().unwrap().play_music(stream, 0.0);
+        Ok(())
+    }
+
+    /// Fades the current music out while `track` fades in over `duration` seconds.
+    pub fn crossfade_to(&mut self, track: &MusicTrack, duration: f32) -> Result<(), AudioError> {
+        let stream = MusicStream::open(track)?;
+        self.mixer.lock().unwrap().play_music(stream, duration);
+        Ok(())
+    }
+
+    pub fn stop_music(&mut self, fade_out: f32) {
+        self.mixer.lock().unwrap().stop_music(fade_out);
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.mixer.lock().unwrap().set_music_volume(volume);
+    }
+
+    pub fn add_bus(&mut self, name: &str) -> BusId {
+        self.mixer.lock().unwrap().add_bus(name)
+    }
+
+    pub fn get_bus_id(&self, name: &str) -> Option<BusId> {
+        self.mixer.lock().unwrap().get_bus_id(name)
+    }
+
+    pub fn get_bus_gain(&self, bus: BusId) -> Option<f32> {
+        Some(self.mixer.lock().unwrap().get_bus(bus)?.get_gain())
+    }
+
+    pub fn set_bus_gain(&mut self, bus: BusId, gain: f32) {
+        if let Some(bus) = self.mixer.lock().unwrap().get_bus(bus) {
+            bus.set_gain(gain);
+        }
+    }
+
+    pub fn set_bus_muted(&mut self, bus: BusId, muted: bool) {
+        if let Some(bus) = self.mixer.lock().unwrap().get_bus(bus) {
+            bus.set_muted(muted);
+        }
+    }
+
+    /// While any bus is soloed only soloed buses can be heard. Soloing master has no effect.
+    pub fn set_bus_solo(&mut self, bus: BusId, soloed: bool) {
+        if let Some(bus) = self.mixer.lock().unwrap().get_bus(bus) {
+            bus.set_soloed(soloed);
+        }
+    }
+
+    /// Appends an effect to the end of the bus' effect chain
+    pub fn add_bus_effect(&mut self, bus: BusId, effect: Box<dyn Effect>) {
+        if let Some(bus) = self.mixer.lock().unwrap().get_bus(bus) {
+            bus.add_effect(effect);
+        }
+    }
+
+    pub fn clear_bus_effects(&mut self, bus: BusId) {
+        if let Some(bus) = self.mixer.lock().unwrap().get_bus(bus) {
+            bus.clear_effects();
+        }
+    }
+
+    /// Returns the playback position of the current track in seconds, None if no music is playing
+    pub fn music_position(&self) -> Option<f32> {
+        self.mixer.lock().unwrap().music_position()
     }
 }
@@ -0,0 +1,202 @@
+use crate::audio::audio_manager::AudioError;
+use crate::audio::mixer::{Mixer, OUTPUT_CHANNELS};
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use std::sync::{Arc, Mutex};
+
+/// Something that consumes the mixed output of the `AudioManager`.
+///
+/// A backend is handed the shared mixer once in `start` and from then on pulls interleaved
+/// stereo frames from it at its own pace, usually from an audio thread.
+pub trait AudioBackend {
+    /// The sample rate in Hz the mixer should render at for this backend.
+    fn sample_rate(&self) -> u32;
+
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), AudioError>;
+}
+
+/// Plays audio through the default output device of the system.
+pub struct DeviceBackend {
+    device: cpal::Device,
+    config: cpal::SupportedStreamConfig,
+    stream: Option<cpal::Stream>,
+}
+
+impl DeviceBackend {
+    pub fn new() -> Result<Self, AudioError> {
+        let host = cpal::default_host();
+        let device = host.default_output_device()
+            .ok_or(AudioError::BackendError("No output device available".to_string()))?;
+        let config = device.default_output_config()
+            .map_err(|e| AudioError::BackendError(e.to_string()))?;
+
+        Ok(Self {
+            device,
+            config,
+            stream: None,
+        })
+    }
+
+    fn build_stream<T>(&self, mixer: Arc<Mutex<Mixer>>) -> Result<cpal::Stream, AudioError>
+    where
+        T: SizedSample + FromSample<f32>,
+    {
+        let config = self.config.config();
+        let channels = config.channels as usize;
+        let mut scratch: Vec<f32> = Vec::new();
+
+        self.device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let frames = data.len() / channels;
+                scratch.resize(frames * OUTPUT_CHANNELS, 0.0);
+
+                match mixer.lock() {
+                    Ok(mut mixer) => mixer.render(&mut scratch),
+                    Err(_) => scratch.fill(0.0),
+                }
+
+                for (frame, stereo) in data.chunks_exact_mut(channels).zip(scratch.chunks_exact(OUTPUT_CHANNELS)) {
+                    if channels == 1 {
+                        frame[0] = T::from_sample((stereo[0] + stereo[1]) * 0.5);
+                        continue;
+                    }
+                    for (channel, sample) in frame.iter_mut().enumerate() {
+                        *sample = T::from_sample(stereo.get(channel).copied().unwrap_or(0.0));
+                    }
+                }
+            },
+            |err| eprintln!("Error: Audio stream failed:\n\t{}", err),
+            None,
+        ).map_err(|e| AudioError::BackendError(e.to_string()))
+    }
+}
+
+impl AudioBackend for DeviceBackend {
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate()
+    }
+
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), AudioError> {
+        let stream = match self.config.sample_format() {
+            SampleFormat::F32 => self.build_stream::<f32>(mixer)?,
+            SampleFormat::I16 => self.build_stream::<i16>(mixer)?,
+            SampleFormat::I32 => self.build_stream::<i32>(mixer)?,
+            SampleFormat::U16 => self.build_stream::<u16>(mixer)?,
+            format => return Err(AudioError::UnsupportedFormat(
+                format!("Output device uses an unsupported sample format: {}", format)
+            )),
+        };
+
+        stream.play().map_err(|e| AudioError::BackendError(e.to_string()))?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
+
+/// A backend without a sound device. Nothing is heard, instead the mixed output is rendered
+/// on demand by calling `render`, which makes it usable on machines without a sound card.
+///
+/// Clones share the same mixer, so a clone can be kept around to render after the original
+/// has been given to the `AudioManager`.
+#[derive(Clone)]
+pub struct OfflineBackend {
+    sample_rate: u32,
+    mixer: Arc<Mutex<Option<Arc<Mutex<Mixer>>>>>,
+}
+
+impl Default for OfflineBackend {
+    fn default() -> Self {
+        Self::new(48_000)
+    }
+}
+
+impl OfflineBackend {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            mixer: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Renders the next `frames` frames of mixed output as interleaved stereo samples.
+    /// Returns silence if the backend hasn't been started yet.
+    pub fn render(&self, frames: usize) -> Vec<f32> {
+        let mut buffer = vec![0.0; frames * OUTPUT_CHANNELS];
+        if let Some(mixer) = self.mixer.lock().unwrap().as_ref() {
+            mixer.lock().unwrap().render(&mut buffer);
+        }
+        buffer
+    }
+}
+
+impl AudioBackend for OfflineBackend {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), AudioError> {
+        *self.mixer.lock().unwrap() = Some(mixer);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::audio_manager::AudioManager;
+
+    const SAMPLE_RATE: u32 = 8_000;
+
+    fn offline_manager() -> (AudioManager, OfflineBackend) {
+        let backend = OfflineBackend::new(SAMPLE_RATE);
+        (AudioManager::with_backend(Box::new(backend.clone())), backend)
+    }
+
+    /// A mono 16 bit PCM WAV file
+    fn wav_bytes(samples: &[i16]) -> Vec<u8> {
+        let data_length = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_length).to_le_bytes());
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_length.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn render_is_silent_before_start() {
+        let backend = OfflineBackend::new(SAMPLE_RATE);
+        assert_eq!(backend.render(4), vec![0.0; 8]);
+    }
+
+    #[test]
+    fn renders_wav_file_unchanged() {
+        let path = std::env::temp_dir().join(format!("redgir_offline_backend_{}.wav", std::process::id()));
+        std::fs::write(&path, wav_bytes(&[0, 8192, -8192, 16384])).unwrap();
+
+        let (mut audio, backend) = offline_manager();
+        let sound = audio.load_sound(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let voice = audio.play_sound(sound).unwrap();
+
+        // mono is played on both sides, then the voice finishes and the output is silent
+        let expected = [0.0, 0.0, 0.25, 0.25, -0.25, -0.25, 0.5, 0.5, 0.0, 0.0, 0.0, 0.0];
+        assert_eq!(backend.render(6), expected);
+        assert!(!voice.is_playing());
+        assert_eq!(audio.active_voices(), 0);
+    }
+}
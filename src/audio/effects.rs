@@ -0,0 +1,422 @@
+use crate::audio::mixer::OUTPUT_CHANNELS;
+
+use std::f32::consts::PI;
+
+/// A DSP stage in the effect chain of a bus. Buffers are interleaved stereo.
+pub trait Effect: Send {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32);
+
+    /// Clears any internal state such as filter history or reverb tails
+    fn reset(&mut self) {}
+}
+
+fn decibels_to_gain(decibels: f32) -> f32 {
+    10.0_f32.powf(decibels / 20.0)
+}
+
+fn gain_to_decibels(gain: f32) -> f32 {
+    20.0 * gain.max(1e-6).log10()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FilterKind {
+    LowPass,
+    HighPass,
+}
+
+/// Second order filter using the coefficients from the RBJ audio EQ cookbook
+#[derive(Clone, Debug)]
+struct Biquad {
+    kind: FilterKind,
+    cutoff: f32,
+    q: f32,
+    sample_rate: u32,
+    coefficients: [f32; 5],
+    /// (x1, x2, y1, y2) for each channel
+    history: [[f32; 4]; OUTPUT_CHANNELS],
+}
+
+impl Biquad {
+    fn new(kind: FilterKind, cutoff: f32, q: f32) -> Self {
+        Self {
+            kind,
+            cutoff,
+            q,
+            sample_rate: 0,
+            coefficients: [1.0, 0.0, 0.0, 0.0, 0.0],
+            history: [[0.0; 4]; OUTPUT_CHANNELS],
+        }
+    }
+
+    fn update_coefficients(&mut self, sample_rate: u32) {
+        let nyquist = sample_rate as f32 / 2.0;
+        let omega = 2.0 * PI * self.cutoff.clamp(10.0, nyquist * 0.99) / sample_rate as f32;
+        let alpha = omega.sin() / (2.0 * self.q.max(0.01));
+        let cos = omega.cos();
+
+        let (b0, b1, b2) = match self.kind {
+            FilterKind::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0),
+            FilterKind::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0),
+        };
+        let a0 = 1.0 + alpha;
+        let a1 = -2.0 * cos;
+        let a2 = 1.0 - alpha;
+
+        self.coefficients = [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0];
+        self.sample_rate = sample_rate;
+    }
+
+    fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff;
+        // forces the coefficients to be recalculated on the next call to process
+        self.sample_rate = 0;
+    }
+
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        if self.sample_rate != sample_rate {
+            self.update_coefficients(sample_rate);
+        }
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+
+        for frame in buffer.chunks_exact_mut(OUTPUT_CHANNELS) {
+            for (sample, history) in frame.iter_mut().zip(self.history.iter_mut()) {
+                let [x1, x2, y1, y2] = *history;
+                let x = *sample;
+                let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+                *history = [x, x1, y, y1];
+                *sample = y;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.history = [[0.0; 4]; OUTPUT_CHANNELS];
+    }
+}
+
+/// Removes frequencies above the cutoff, useful for muffling sounds e.g. when the game is paused
+pub struct LowPassFilter {
+    filter: Biquad,
+}
+
+impl LowPassFilter {
+    pub fn new(cutoff: f32) -> Self {
+        Self::with_resonance(cutoff, std::f32::consts::FRAC_1_SQRT_2)
+    }
+
+    /// A `q` above 0.707 adds a resonant peak at the cutoff frequency
+    pub fn with_resonance(cutoff: f32, q: f32) -> Self {
+        Self {
+            filter: Biquad::new(FilterKind::LowPass, cutoff, q),
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.filter.set_cutoff(cutoff);
+    }
+}
+
+impl Effect for LowPassFilter {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        self.filter.process(buffer, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+    }
+}
+
+/// Removes frequencies below the cutoff, useful for thin radio or telephone sounds
+pub struct HighPassFilter {
+    filter: Biquad,
+}
+
+impl HighPassFilter {
+    pub fn new(cutoff: f32) -> Self {
+        Self::with_resonance(cutoff, std::f32::consts::FRAC_1_SQRT_2)
+    }
+
+    /// A `q` above 0.707 adds a resonant peak at the cutoff frequency
+    pub fn with_resonance(cutoff: f32, q: f32) -> Self {
+        Self {
+            filter: Biquad::new(FilterKind::HighPass, cutoff, q),
+        }
+    }
+
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.filter.set_cutoff(cutoff);
+    }
+}
+
+impl Effect for HighPassFilter {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        self.filter.process(buffer, sample_rate);
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+    }
+}
+
+/// Feedback comb filter with a one pole low pass in the loop
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filter_state: f32,
+}
+
+impl Comb {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length.max(1)],
+            index: 0,
+            filter_state: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filter_state = output * (1.0 - damping) + self.filter_state * damping;
+        self.buffer[self.index] = input + self.filter_state * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+struct AllPass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl AllPass {
+    fn new(length: usize) -> Self {
+        Self {
+            buffer: vec![0.0; length.max(1)],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        let output = delayed - input;
+        self.buffer[self.index] = input + delayed * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+// Delay lengths from freeverb, tuned for 44.1kHz and scaled to the actual sample rate
+const COMB_LENGTHS: [usize; 4] = [1116, 1188, 1277, 1356];
+const ALL_PASS_LENGTHS: [usize; 2] = [556, 441];
+const STEREO_SPREAD: usize = 23;
+
+/// A small freeverb style reverb: parallel comb filters followed by all pass filters
+pub struct Reverb {
+    room_size: f32,
+    damping: f32,
+    wet: f32,
+    sample_rate: u32,
+    combs: Vec<[Comb; OUTPUT_CHANNELS]>,
+    all_passes: Vec<[AllPass; OUTPUT_CHANNELS]>,
+}
+
+impl Reverb {
+    /// `room_size` and `damping` range from 0.0 to 1.0, `wet` is the mix of reverb to dry signal
+    pub fn new(room_size: f32, damping: f32, wet: f32) -> Self {
+        Self {
+            room_size: room_size.clamp(0.0, 1.0),
+            damping: damping.clamp(0.0, 1.0),
+            wet: wet.clamp(0.0, 1.0),
+            sample_rate: 0,
+            combs: Vec::new(),
+            all_passes: Vec::new(),
+        }
+    }
+
+    pub fn set_wet(&mut self, wet: f32) {
+        self.wet = wet.clamp(0.0, 1.0);
+    }
+
+    fn allocate(&mut self, sample_rate: u32) {
+        let scale = |length: usize| length * sample_rate as usize / 44_100;
+        self.combs = COMB_LENGTHS.iter()
+            .map(|&length| [Comb::new(scale(length)), Comb::new(scale(length + STEREO_SPREAD))])
+            .collect();
+        self.all_passes = ALL_PASS_LENGTHS.iter()
+            .map(|&length| [AllPass::new(scale(length)), AllPass::new(scale(length + STEREO_SPREAD))])
+            .collect();
+        self.sample_rate = sample_rate;
+    }
+}
+
+impl Effect for Reverb {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        if self.sample_rate != sample_rate {
+            self.allocate(sample_rate);
+        }
+
+        let feedback = 0.7 + self.room_size * 0.28;
+        let damping = self.damping * 0.4;
+        // the comb filters are summed, so scale the input down to keep the tail from clipping
+        let input_gain = 0.015 * COMB_LENGTHS.len() as f32;
+
+        for frame in buffer.chunks_exact_mut(OUTPUT_CHANNELS) {
+            let input = (frame[0] + frame[1]) * 0.5 * input_gain;
+
+            for channel in 0..OUTPUT_CHANNELS {
+                let mut output: f32 = self.combs.iter_mut()
+                    .map(|combs| combs[channel].process(input, feedback, damping))
+                    .sum();
+                for all_passes in self.all_passes.iter_mut() {
+                    output = all_passes[channel].process(output);
+                }
+                frame[channel] = frame[channel] * (1.0 - self.wet) + output * self.wet;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sample_rate = 0;
+    }
+}
+
+/// Reduces the volume of anything louder than the threshold. Both channels are compressed by
+/// the same amount so the stereo image doesn't shift.
+pub struct Compressor {
+    threshold_db: f32,
+    ratio: f32,
+    attack_seconds: f32,
+    release_seconds: f32,
+    makeup_db: f32,
+    envelope_db: f32,
+}
+
+impl Compressor {
+    /// `ratio` of 4.0 means every 4dB above the threshold only comes out as 1dB
+    pub fn new(threshold_db: f32, ratio: f32, attack_seconds: f32, release_seconds: f32) -> Self {
+        Self {
+            threshold_db,
+            ratio: ratio.max(1.0),
+            attack_seconds,
+            release_seconds,
+            makeup_db: 0.0,
+            envelope_db: -120.0,
+        }
+    }
+
+    /// A compressor with an infinite ratio and a near instant attack, meant to sit at the end
+    /// of the master bus so the mix never goes over `ceiling_db`
+    pub fn limiter(ceiling_db: f32) -> Self {
+        Self::new(ceiling_db, f32::INFINITY, 0.0005, 0.1)
+    }
+
+    pub fn set_makeup_gain(&mut self, makeup_db: f32) {
+        self.makeup_db = makeup_db;
+    }
+
+    /// Gain reduction currently applied in dB, useful for metering
+    pub fn get_gain_reduction(&self) -> f32 {
+        (self.envelope_db - self.threshold_db).max(0.0) * (1.0 - 1.0 / self.ratio)
+    }
+}
+
+impl Effect for Compressor {
+    fn process(&mut self, buffer: &mut [f32], sample_rate: u32) {
+        let coefficient = |seconds: f32| match seconds > 0.0 {
+            true => (-1.0 / (seconds * sample_rate as f32)).exp(),
+            false => 0.0,
+        };
+        let attack = coefficient(self.attack_seconds);
+        let release = coefficient(self.release_seconds);
+        let makeup = decibels_to_gain(self.makeup_db);
+
+        for frame in buffer.chunks_exact_mut(OUTPUT_CHANNELS) {
+            let peak = frame.iter().fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+            let level_db = gain_to_decibels(peak);
+
+            let smoothing = if level_db > self.envelope_db { attack } else { release };
+            self.envelope_db = level_db + (self.envelope_db - level_db) * smoothing;
+
+            let gain = decibels_to_gain(-self.get_gain_reduction()) * makeup;
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope_db = -120.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::audio_manager::AudioManager;
+    use crate::audio::backend::OfflineBackend;
+    use crate::audio::bus::BusId;
+    use crate::audio::mixer::SoundBuffer;
+
+    const SAMPLE_RATE: u32 = 48_000;
+
+    /// Plays `samples` on the sfx bus with `effect` added to `bus` and returns the left channel
+    fn render_through(effect: Box<dyn Effect>, bus: BusId, samples: Vec<f32>) -> Vec<f32> {
+        let backend = OfflineBackend::new(SAMPLE_RATE);
+        let mut audio = AudioManager::with_backend(Box::new(backend.clone()));
+        audio.add_bus_effect(bus, effect);
+
+        let frames = samples.len();
+        let sound = audio.add_sound(SoundBuffer::new(samples, 1, SAMPLE_RATE));
+        audio.play_sound(sound).unwrap();
+        backend.render(frames).into_iter().step_by(OUTPUT_CHANNELS).collect()
+    }
+
+    fn sine(frequency: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|frame| amplitude * (2.0 * PI * frequency * frame as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    /// Output RMS divided by input RMS, skipping the first half while the filter settles
+    fn filter_gain(effect: Box<dyn Effect>, frequency: f32) -> f32 {
+        let input = sine(frequency, 0.5, 9_600);
+        let output = render_through(effect, BusId::SFX, input.clone());
+        let rms = |samples: &[f32]| (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt();
+        rms(&output[4_800..]) / rms(&input[4_800..])
+    }
+
+    #[test]
+    fn low_pass_keeps_low_frequencies() {
+        assert!(filter_gain(Box::new(LowPassFilter::new(1_000.0)), 100.0) > 0.95);
+        assert!(filter_gain(Box::new(LowPassFilter::new(1_000.0)), 10_000.0) < 0.02);
+    }
+
+    #[test]
+    fn high_pass_keeps_high_frequencies() {
+        assert!(filter_gain(Box::new(HighPassFilter::new(1_000.0)), 10_000.0) > 0.95);
+        assert!(filter_gain(Box::new(HighPassFilter::new(1_000.0)), 100.0) < 0.02);
+    }
+
+    #[test]
+    fn filter_is_at_minus_3db_at_cutoff() {
+        let gain = filter_gain(Box::new(LowPassFilter::new(1_000.0)), 1_000.0);
+        assert!((gain - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.02, "{}", gain);
+    }
+
+    #[test]
+    fn limiter_holds_the_ceiling() {
+        let ceiling = decibels_to_gain(-6.0);
+        let output = render_through(Box::new(Compressor::limiter(-6.0)), BusId::MASTER, vec![0.9; 4_800]);
+
+        // the attack takes a few samples to catch up, after that nothing goes over
+        assert!(output[480..].iter().all(|sample| *sample <= ceiling * 1.01), "{:?}", &output[480..490]);
+        assert!(output[480..].iter().all(|sample| *sample >= ceiling * 0.95));
+    }
+
+    #[test]
+    fn limiter_leaves_quiet_signals_alone() {
+        let output = render_through(Box::new(Compressor::limiter(-6.0)), BusId::MASTER, sine(440.0, 0.25, 4_800));
+        let input = sine(440.0, 0.25, 4_800);
+        assert!(output.iter().zip(input.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,556 @@
+use crate::audio::bus::{Bus, BusId};
+use crate::audio::music::MusicStream;
+use crate::audio::audio_manager::AudioError;
+use crate::utility::file_parser;
+
+use std::sync::{Arc, Mutex};
+
+/// Number of interleaved channels the mixer renders. Output is always stereo.
+pub const OUTPUT_CHANNELS: usize = 2;
+/// Slowest playback rate a voice can have, lower rates would never finish playing
+pub const MIN_PLAYBACK_RATE: f32 = 0.01;
+
+/// Fully decoded audio kept in memory, samples are interleaved and normalized to [-1.0, 1.0].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundBuffer {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl SoundBuffer {
+    pub fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        Self {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub fn get_channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn get_samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    pub fn duration_seconds(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    /// Saves the sound as a 16 bit WAV file
+    pub fn save_wav(&self, path: &str) -> Result<(), AudioError> {
+        file_parser::save_samples_to_wav(path, self.sample_rate, self.channels, &self.samples)
+    }
+
+    /// Returns the (left, right) pair for a frame, mono sounds are played on both sides.
+    pub fn get_frame(&self, index: usize) -> (f32, f32) {
+        let base = index * self.channels as usize;
+        match self.channels {
+            1 => (self.samples[base], self.samples[base]),
+            _ => (self.samples[base], self.samples[base + 1]),
+        }
+    }
+}
+
+/// How the mixer makes room for a new voice once the voice limit has been reached.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum VoiceStealing {
+    /// Stop the voice that started playing first
+    Oldest,
+    /// Stop the voice with the lowest volume
+    Quietest,
+}
+
+/// Initial parameters of a voice, they can all be changed afterwards through its `VoiceHandle`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VoiceSettings {
+    /// Linear gain, 1.0 plays the sound unchanged
+    pub volume: f32,
+    /// -1.0 is fully left, 0.0 is centered and 1.0 is fully right
+    pub pan: f32,
+    /// Speed multiplier, also changes the pitch. 2.0 plays an octave higher. Raised to
+    /// `MIN_PLAYBACK_RATE` if lower
+    pub playback_rate: f32,
+    pub looping: bool,
+    pub bus: BusId,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.0,
+            playback_rate: 1.0,
+            looping: false,
+            bus: BusId::SFX,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub struct VoiceId {
+    id: u64,
+}
+
+struct Voice {
+    id: VoiceId,
+    sound: Arc<SoundBuffer>,
+    /// Playback cursor in frames of the source sound
+    position: f64,
+    settings: VoiceSettings,
+    is_paused: bool,
+    is_stopped: bool,
+}
+
+impl Voice {
+    fn new(id: VoiceId, sound: Arc<SoundBuffer>, settings: VoiceSettings) -> Self {
+        Self {
+            id,
+            sound,
+            position: 0.0,
+            settings,
+            is_paused: false,
+            is_stopped: false,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.is_stopped || self.position >= self.sound.frames() as f64
+    }
+
+    /// Left and right gain for the current volume and pan. Centered sounds play at full volume
+    /// on both sides and panning attenuates the opposite side.
+    fn gains(&self) -> (f32, f32) {
+        let pan = self.settings.pan.clamp(-1.0, 1.0);
+        let volume = self.settings.volume.max(0.0);
+        (volume * (1.0 - pan).min(1.0), volume * (1.0 + pan).min(1.0))
+    }
+
+    /// Adds this voice into an interleaved stereo buffer, resampling to `output_rate`.
+    fn mix_into(&mut self, out: &mut [f32], output_rate: u32) {
+        if self.is_paused {
+            return;
+        }
+
+        let step = self.sound.get_sample_rate() as f64 / output_rate as f64
+            * self.settings.playback_rate as f64;
+        let frames = self.sound.frames();
+        let (left_gain, right_gain) = self.gains();
+
+        for frame in out.chunks_exact_mut(OUTPUT_CHANNELS) {
+            if self.settings.looping && self.position >= frames as f64 {
+                self.position %= frames as f64;
+            }
+            if self.is_finished() {
+                break;
+            }
+
+            // linear interpolation between the two closest source frames
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let next = match index + 1 < frames {
+                true => index + 1,
+                false if self.settings.looping => 0,
+                false => index,
+            };
+            let (left_a, right_a) = self.sound.get_frame(index);
+            let (left_b, right_b) = self.sound.get_frame(next);
+
+            frame[0] += (left_a + (left_b - left_a) * fraction) * left_gain;
+            frame[1] += (right_a + (right_b - right_a) * fraction) * right_gain;
+
+            self.position += step;
+        }
+    }
+}
+
+/// Sums every playing voice into a single interleaved stereo stream.
+pub struct Mixer {
+    sample_rate: u32,
+    voices: Vec<Voice>,
+    last_voice_id: u64,
+    voice_limit: usize,
+    voice_stealing: VoiceStealing,
+    /// The last stream is the current track, any others are still fading out
+    music: Vec<MusicStream>,
+    /// Indexed by `BusId`, master is always first
+    buses: Vec<Bus>,
+}
+
+impl Mixer {
+    pub const DEFAULT_VOICE_LIMIT: usize = 64;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            voices: Vec::new(),
+            last_voice_id: 0,
+            voice_limit: Self::DEFAULT_VOICE_LIMIT,
+            voice_stealing: VoiceStealing::Oldest,
+            music: Vec::new(),
+            buses: vec![Bus::new("master"), Bus::new("music"), Bus::new("sfx"), Bus::new("voice")],
+        }
+    }
+
+    pub fn add_bus(&mut self, name: &str) -> BusId {
+        self.buses.push(Bus::new(name));
+        BusId::from_index(self.buses.len() - 1)
+    }
+
+    pub fn get_bus_id(&self, name: &str) -> Option<BusId> {
+        self.buses.iter()
+            .position(|bus| bus.get_name() == name)
+            .map(BusId::from_index)
+    }
+
+    pub fn get_bus(&mut self, bus: BusId) -> Option<&mut Bus> {
+        self.buses.get_mut(bus.index())
+    }
+
+    pub fn get_sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn active_voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Limits how many voices can play at once. When a new voice would exceed the limit an
+    /// existing voice is stopped according to `stealing`.
+    pub fn set_voice_limit(&mut self, limit: usize, stealing: VoiceStealing) {
+        self.voice_limit = limit;
+        self.voice_stealing = stealing;
+        while self.voices.len() > self.voice_limit {
+            self.steal_voice();
+        }
+    }
+
+    pub fn get_voice_limit(&self) -> (usize, VoiceStealing) {
+        (self.voice_limit, self.voice_stealing)
+    }
+
+    fn steal_voice(&mut self) {
+        // voices are pushed in the order they are played, so the oldest voice is always first
+        let victim = match self.voice_stealing {
+            VoiceStealing::Oldest => 0,
+            VoiceStealing::Quietest => self.voices.iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.settings.volume.total_cmp(&b.settings.volume))
+                .map(|(index, _)| index)
+                .unwrap_or(0),
+        };
+        if victim < self.voices.len() {
+            self.voices.remove(victim);
+        }
+    }
+
+    pub fn play(&mut self, sound: Arc<SoundBuffer>, settings: VoiceSettings) -> VoiceId {
+        let id = VoiceId { id: self.last_voice_id };
+        self.last_voice_id += 1;
+
+        if sound.frames() == 0 || self.voice_limit == 0 {
+            return id;
+        }
+        while self.voices.len() >= self.voice_limit {
+            self.steal_voice();
+        }
+        let settings = VoiceSettings {
+            playback_rate: settings.playback_rate.max(MIN_PLAYBACK_RATE),
+            ..settings
+        };
+        self.voices.push(Voice::new(id, sound, settings));
+        id
+    }
+
+    fn get_voice(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices.iter_mut().find(|voice| voice.id == id)
+    }
+
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voices.iter().any(|voice| voice.id == id && !voice.is_finished())
+    }
+
+    pub fn get_settings(&self, id: VoiceId) -> Option<VoiceSettings> {
+        self.voices.iter().find(|voice| voice.id == id).map(|voice| voice.settings)
+    }
+
+    pub fn set_volume(&mut self, id: VoiceId, volume: f32) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.settings.volume = volume;
+        }
+    }
+
+    pub fn set_pan(&mut self, id: VoiceId, pan: f32) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.settings.pan = pan;
+        }
+    }
+
+    pub fn set_playback_rate(&mut self, id: VoiceId, playback_rate: f32) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.settings.playback_rate = playback_rate.max(MIN_PLAYBACK_RATE);
+        }
+    }
+
+    pub fn set_looping(&mut self, id: VoiceId, looping: bool) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.settings.looping = looping;
+        }
+    }
+
+    pub fn set_bus(&mut self, id: VoiceId, bus: BusId) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.settings.bus = bus;
+        }
+    }
+
+    pub fn set_paused(&mut self, id: VoiceId, paused: bool) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.is_paused = paused;
+        }
+    }
+
+    pub fn stop(&mut self, id: VoiceId) {
+        if let Some(voice) = self.get_voice(id) {
+            voice.is_stopped = true;
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Starts a new music track. With a `fade_seconds` above 0.0 the new track fades in while
+    /// the current one fades out, otherwise the current track is cut off.
+    pub fn play_music(&mut self, mut stream: MusicStream, fade_seconds: f32) {
+        self.stop_music(fade_seconds);
+        if fade_seconds > 0.0 {
+            stream.set_fade_gain(0.0);
+            stream.fade_to(1.0, fade_seconds, self.sample_rate);
+        }
+        self.music.push(stream);
+    }
+
+    pub fn stop_music(&mut self, fade_seconds: f32) {
+        if fade_seconds <= 0.0 {
+            self.music.clear();
+            return;
+        }
+        for stream in self.music.iter_mut() {
+            stream.fade_to(0.0, fade_seconds, self.sample_rate);
+        }
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        if let Some(stream) = self.music.last_mut() {
+            stream.set_volume(volume);
+        }
+    }
+
+    /// Playback position of the current track in seconds
+    pub fn music_position(&self) -> Option<f32> {
+        self.music.last().map(|stream| stream.position_seconds())
+    }
+
+    /// Overwrites `out` with the next `out.len() / OUTPUT_CHANNELS` frames of mixed audio.
+    pub fn render(&mut self, out: &mut [f32]) {
+        out.fill(0.0);
+        for bus in self.buses.iter_mut() {
+            bus.begin(out.len());
+        }
+
+        // voices routed to a bus that doesn't exist fall back to sfx
+        for voice in self.voices.iter_mut() {
+            let bus = match self.buses.get_mut(voice.settings.bus.index()) {
+                Some(bus) => bus,
+                None => &mut self.buses[BusId::SFX.index()],
+            };
+            voice.mix_into(bus.buffer_mut(), self.sample_rate);
+        }
+        self.voices.retain(|voice| !voice.is_finished());
+
+        for stream in self.music.iter_mut() {
+            stream.mix_into(self.buses[BusId::MUSIC.index()].buffer_mut(), self.sample_rate);
+        }
+        self.music.retain(|stream| !stream.is_finished());
+
+        // when any bus is soloed only soloed buses are heard
+        let (master, buses) = self.buses.split_at_mut(1);
+        let any_soloed = buses.iter().any(|bus| bus.is_soloed());
+        for bus in buses.iter_mut() {
+            let is_audible = !bus.is_muted() && (!any_soloed || bus.is_soloed());
+            bus.finish_into(out, self.sample_rate, is_audible);
+        }
+
+        // anything routed straight to master is added before the master effect chain
+        let master = &mut master[0];
+        for (sample, master_sample) in out.iter_mut().zip(master.buffer_mut().iter()) {
+            *sample += master_sample;
+        }
+        master.process_in_place(out, self.sample_rate);
+
+        for sample in out.iter_mut() {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Controls a single playing sound. Handles stay valid after the sound finishes, at which point
+/// every call is ignored.
+#[derive(Clone)]
+pub struct VoiceHandle {
+    id: VoiceId,
+    mixer: Arc<Mutex<Mixer>>,
+}
+
+impl VoiceHandle {
+    pub fn new(id: VoiceId, mixer: Arc<Mutex<Mixer>>) -> Self {
+        Self {
+            id,
+            mixer,
+        }
+    }
+
+    pub fn id(&self) -> VoiceId {
+        self.id
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.mixer.lock().unwrap().is_playing(self.id)
+    }
+
+    /// Returns None once the voice has finished
+    pub fn get_settings(&self) -> Option<VoiceSettings> {
+        self.mixer.lock().unwrap().get_settings(self.id)
+    }
+
+    pub fn set_volume(&self, volume: f32) -> &Self {
+        self.mixer.lock().unwrap().set_volume(self.id, volume);
+        self
+    }
+
+    pub fn set_pan(&self, pan: f32) -> &Self {
+        self.mixer.lock().unwrap().set_pan(self.id, pan);
+        self
+    }
+
+    pub fn set_playback_rate(&self, playback_rate: f32) -> &Self {
+        self.mixer.lock().unwrap().set_playback_rate(self.id, playback_rate);
+        self
+    }
+
+    pub fn set_looping(&self, looping: bool) -> &Self {
+        self.mixer.lock().unwrap().set_looping(self.id, looping);
+        self
+    }
+
+    /// Moves the voice to another bus, it will use that bus' volume and effects from now on
+    pub fn set_bus(&self, bus: BusId) -> &Self {
+        self.mixer.lock().unwrap().set_bus(self.id, bus);
+        self
+    }
+
+    pub fn pause(&self) -> &Self {
+        self.mixer.lock().unwrap().set_paused(self.id, true);
+        self
+    }
+
+    pub fn resume(&self) -> &Self {
+        self.mixer.lock().unwrap().set_paused(self.id, false);
+        self
+    }
+
+    pub fn stop(&self) {
+        self.mixer.lock().unwrap().stop(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant_sound(value: f32) -> Arc<SoundBuffer> {
+        Arc::new(SoundBuffer::new(vec![value; 64], 1, 100))
+    }
+
+    /// First output frame as (left, right)
+    fn render_frame(mixer: &mut Mixer) -> (f32, f32) {
+        let mut out = [0.0; OUTPUT_CHANNELS];
+        mixer.render(&mut out);
+        (out[0], out[1])
+    }
+
+    #[test]
+    fn non_positive_playback_rate_still_finishes() {
+        let mut mixer = Mixer::new(100);
+        let sound = Arc::new(SoundBuffer::new(vec![0.5; 4], 1, 100));
+        let stopped = mixer.play(sound.clone(), VoiceSettings { playback_rate: 0.0, ..Default::default() });
+        let reversed = mixer.play(sound, VoiceSettings::default());
+        mixer.set_playback_rate(reversed, -2.0);
+
+        assert_eq!(mixer.get_settings(stopped).unwrap().playback_rate, MIN_PLAYBACK_RATE);
+        assert_eq!(mixer.get_settings(reversed).unwrap().playback_rate, MIN_PLAYBACK_RATE);
+
+        let mut out = vec![0.0; (4.0 / MIN_PLAYBACK_RATE) as usize * OUTPUT_CHANNELS + OUTPUT_CHANNELS];
+        mixer.render(&mut out);
+        assert_eq!(mixer.active_voices(), 0);
+    }
+
+    #[test]
+    fn oldest_voice_is_stolen() {
+        let mut mixer = Mixer::new(100);
+        mixer.set_voice_limit(2, VoiceStealing::Oldest);
+
+        let voices: Vec<VoiceId> = [0.1, 0.2, 0.4].iter()
+            .map(|&value| mixer.play(constant_sound(value), VoiceSettings::default()))
+            .collect();
+
+        assert_eq!(mixer.active_voices(), 2);
+        assert!(!mixer.is_playing(voices[0]));
+        assert!(mixer.is_playing(voices[1]) && mixer.is_playing(voices[2]));
+        let (left, _) = render_frame(&mut mixer);
+        assert!((left - 0.6).abs() < 1e-6, "{}", left);
+    }
+
+    #[test]
+    fn quietest_voice_is_stolen() {
+        let mut mixer = Mixer::new(100);
+        mixer.set_voice_limit(2, VoiceStealing::Quietest);
+        let sound = constant_sound(0.4);
+
+        let voices: Vec<VoiceId> = [0.5, 1.0, 0.25].iter()
+            .map(|&volume| mixer.play(sound.clone(), VoiceSettings { volume, ..Default::default() }))
+            .collect();
+
+        assert!(!mixer.is_playing(voices[0]));
+        assert!(mixer.is_playing(voices[1]) && mixer.is_playing(voices[2]));
+        let (left, _) = render_frame(&mut mixer);
+        assert!((left - 0.5).abs() < 1e-6, "{}", left);
+    }
+
+    #[test]
+    fn stop_all_silences_every_voice() {
+        let mut mixer = Mixer::new(100);
+        let sound = constant_sound(0.5);
+        let first = mixer.play(sound.clone(), VoiceSettings::default());
+        let second = mixer.play(sound, VoiceSettings { looping: true, ..Default::default() });
+        assert_ne!(render_frame(&mut mixer), (0.0, 0.0));
+
+        mixer.stop_all();
+        assert_eq!(mixer.active_voices(), 0);
+        assert!(!mixer.is_playing(first) && !mixer.is_playing(second));
+        let mut out = [1.0; 8 * OUTPUT_CHANNELS];
+        mixer.render(&mut out);
+        assert_eq!(out, [0.0; 8 * OUTPUT_CHANNELS]);
+    }
+}
@@ -1 +1,8 @@
 pub mod audio_manager;
+pub mod backend;
+pub mod bus;
+pub mod effects;
+pub mod mixer;
+pub mod music;
+pub mod spatial;
+pub mod synth;
@@ -0,0 +1,436 @@
+use crate::audio::audio_manager::AudioError;
+use crate::audio::mixer::OUTPUT_CHANNELS;
+use crate::utility::file_parser::{self, WavFormat};
+
+use lewton::inside_ogg::OggStreamReader;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::thread;
+
+/// Frames decoded per chunk handed from the decoding thread to the mixer
+const CHUNK_FRAMES: usize = 4096;
+/// Chunks buffered ahead of playback, at 48kHz this is a bit under a second of audio
+const BUFFERED_CHUNKS: usize = 8;
+
+/// A music file that is streamed from disk instead of being decoded into memory.
+/// Supports OGG Vorbis and WAV files.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MusicTrack {
+    path: String,
+    volume: f32,
+    looping: bool,
+    loop_start: f32,
+    loop_end: Option<f32>,
+}
+
+impl MusicTrack {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            volume: 1.0,
+            looping: false,
+            loop_start: 0.0,
+            loop_end: None,
+        }
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn set_volume(&mut self, volume: f32) -> &mut Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Loops the whole track, or the section given by `set_loop_points`
+    pub fn set_looping(&mut self, looping: bool) -> &mut Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Once playback reaches `loop_end` (or the end of the file when None) it jumps back to
+    /// `loop_start`. Both are in seconds. This also enables looping.
+    pub fn set_loop_points(&mut self, loop_start: f32, loop_end: Option<f32>) -> &mut Self {
+        self.looping = true;
+        self.loop_start = loop_start.max(0.0);
+        self.loop_end = loop_end;
+        self
+    }
+}
+
+trait MusicDecoder: Send {
+    fn sample_rate(&self) -> u32;
+
+    fn channels(&self) -> u16;
+
+    /// Returns the next interleaved samples, or None at the end of the stream
+    fn read_chunk(&mut self) -> Result<Option<Vec<f32>>, AudioError>;
+
+    /// Moves the decoder to `frame` and returns the frame it actually ended up at
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioError>;
+}
+
+struct WavDecoder {
+    reader: BufReader<File>,
+    format: WavFormat,
+    data_offset: u64,
+    data_length: u64,
+    bytes_read: u64,
+}
+
+impl WavDecoder {
+    fn new(file: File) -> Result<Self, AudioError> {
+        let mut reader = BufReader::new(file);
+        let (format, data_offset, data_length) = file_parser::read_wav_header(&mut reader)?;
+
+        Ok(Self {
+            reader,
+            format,
+            data_offset,
+            data_length,
+            bytes_read: 0,
+        })
+    }
+}
+
+impl MusicDecoder for WavDecoder {
+    fn sample_rate(&self) -> u32 {
+        self.format.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.format.channels
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<Vec<f32>>, AudioError> {
+        let remaining = self.data_length - self.bytes_read;
+        let length = remaining.min((CHUNK_FRAMES * self.format.bytes_per_frame()) as u64) as usize;
+        if length == 0 {
+            return Ok(None);
+        }
+
+        let mut data = vec![0u8; length];
+        self.reader.read_exact(&mut data)?;
+        self.bytes_read += length as u64;
+        Ok(Some(file_parser::decode_wav_samples(&self.format, &data)?))
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioError> {
+        let bytes_per_frame = self.format.bytes_per_frame() as u64;
+        let offset = (frame * bytes_per_frame).min(self.data_length);
+        self.reader.seek(SeekFrom::Start(self.data_offset + offset))?;
+        self.bytes_read = offset;
+        Ok(offset / bytes_per_frame)
+    }
+}
+
+struct OggDecoder {
+    reader: OggStreamReader<BufReader<File>>,
+}
+
+impl OggDecoder {
+    fn new(file: File) -> Result<Self, AudioError> {
+        let reader = OggStreamReader::new(BufReader::new(file))
+            .map_err(|e| AudioError::InvalidFormat(e.to_string()))?;
+        Ok(Self { reader })
+    }
+}
+
+impl MusicDecoder for OggDecoder {
+    fn sample_rate(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.reader.ident_hdr.audio_channels as u16
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<Vec<f32>>, AudioError> {
+        let mut samples: Vec<f32> = Vec::new();
+        let target = CHUNK_FRAMES * self.channels() as usize;
+
+        while samples.len() < target {
+            let packet = self.reader.read_dec_packet_itl()
+                .map_err(|e| AudioError::InvalidFormat(e.to_string()))?;
+            match packet {
+                Some(packet) => samples.extend(packet.iter().map(|&s| s as f32 / 32_768.0)),
+                None => break,
+            }
+        }
+
+        match samples.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(samples)),
+        }
+    }
+
+    fn seek(&mut self, frame: u64) -> Result<u64, AudioError> {
+        // NOTE vorbis can only seek to the start of a page, so loop points land up to a page early
+        self.reader.seek_absgp_pg(frame)
+            .map_err(|e| AudioError::InvalidFormat(e.to_string()))?;
+        Ok(frame)
+    }
+}
+
+fn open_decoder(path: &str) -> Result<Box<dyn MusicDecoder>, AudioError> {
+    let mut file = File::open(path)?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)
+        .map_err(|_| AudioError::InvalidFormat(format!("{}: file is too small", path)))?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let decoder: Box<dyn MusicDecoder> = match &magic {
+        b"OggS" => Box::new(OggDecoder::new(file)?),
+        b"RIFF" => Box::new(WavDecoder::new(file)?),
+        _ => return Err(AudioError::UnsupportedFormat(
+            format!("{}: music must be an OGG Vorbis or WAV file", path)
+        )),
+    };
+
+    if decoder.channels() == 0 || decoder.sample_rate() == 0 {
+        return Err(AudioError::InvalidFormat(format!("{}: stream has no channels", path)));
+    }
+    Ok(decoder)
+}
+
+struct MusicChunk {
+    /// Frame of the track the first sample of this chunk belongs to
+    start_frame: u64,
+    samples: Vec<f32>,
+}
+
+/// Runs on the decoding thread until the track ends or the `MusicStream` is dropped.
+fn decode_track(mut decoder: Box<dyn MusicDecoder>, loop_range: Option<(u64, Option<u64>)>, sender: SyncSender<MusicChunk>) {
+    let channels = decoder.channels() as usize;
+    let mut frame: u64 = 0;
+
+    loop {
+        let mut samples = match decoder.read_chunk() {
+            Ok(Some(samples)) => samples,
+            Ok(None) => match loop_range {
+                // only loop if something was played since the last jump, otherwise an empty
+                // loop section would spin forever
+                Some((loop_start, _)) if frame > loop_start => {
+                    match decoder.seek(loop_start) {
+                        Ok(position) => frame = position,
+                        Err(err) => {
+                            eprintln!("Error: Failed to loop music:\n\t{}", err);
+                            break;
+                        }
+                    }
+                    continue;
+                },
+                _ => break,
+            },
+            Err(err) => {
+                eprintln!("Error: Failed to decode music:\n\t{}", err);
+                break;
+            },
+        };
+
+        let mut jump_to_start = None;
+        if let Some((loop_start, Some(loop_end))) = loop_range {
+            let frames = (samples.len() / channels) as u64;
+            if loop_end > loop_start && frame + frames >= loop_end {
+                samples.truncate((loop_end.saturating_sub(frame)) as usize * channels);
+                jump_to_start = Some(loop_start);
+            }
+        }
+
+        let chunk = MusicChunk { start_frame: frame, samples };
+        frame += (chunk.samples.len() / channels) as u64;
+        // the receiver is gone once the track stops playing
+        if sender.send(chunk).is_err() {
+            break;
+        }
+
+        if let Some(loop_start) = jump_to_start {
+            match decoder.seek(loop_start) {
+                Ok(position) => frame = position,
+                Err(err) => {
+                    eprintln!("Error: Failed to loop music:\n\t{}", err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The mixer side of a streamed track, receives decoded chunks from the decoding thread.
+pub struct MusicStream {
+    receiver: Receiver<MusicChunk>,
+    channels: usize,
+    sample_rate: u32,
+    current: Option<MusicChunk>,
+    next: Option<MusicChunk>,
+    /// Fractional frame inside of `current`
+    cursor: f64,
+    position: u64,
+    is_decoder_done: bool,
+    volume: f32,
+    fade_gain: f32,
+    fade_target: f32,
+    fade_step: f32,
+}
+
+impl MusicStream {
+    /// Opens the track and starts decoding it on a separate thread.
+    pub fn open(track: &MusicTrack) -> Result<Self, AudioError> {
+        let decoder = open_decoder(&track.path)?;
+        let sample_rate = decoder.sample_rate();
+        let channels = decoder.channels() as usize;
+
+        let loop_range = match track.looping {
+            true => Some((
+                (track.loop_start * sample_rate as f32) as u64,
+                track.loop_end.map(|end| (end * sample_rate as f32) as u64),
+            )),
+            false => None,
+        };
+
+        let (sender, receiver) = mpsc::sync_channel(BUFFERED_CHUNKS);
+        thread::Builder::new()
+            .name("redgir-music".to_string())
+            .spawn(move || decode_track(decoder, loop_range, sender))?;
+
+        Ok(Self {
+            receiver,
+            channels,
+            sample_rate,
+            current: None,
+            next: None,
+            cursor: 0.0,
+            position: 0,
+            is_decoder_done: false,
+            volume: track.volume,
+            fade_gain: 1.0,
+            fade_target: 1.0,
+            fade_step: 0.0,
+        })
+    }
+
+    /// Position of playback in seconds, loops jump back to the loop start
+    pub fn position_seconds(&self) -> f32 {
+        self.position as f32 / self.sample_rate as f32
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    /// Ramps the fade gain from its current value to `target` over `seconds`
+    pub fn fade_to(&mut self, target: f32, seconds: f32, output_rate: u32) {
+        self.fade_target = target;
+        if seconds <= 0.0 {
+            self.fade_gain = target;
+            self.fade_step = 0.0;
+        } else {
+            self.fade_step = (target - self.fade_gain).abs() / (seconds * output_rate as f32);
+        }
+    }
+
+    pub fn set_fade_gain(&mut self, gain: f32) {
+        self.fade_gain = gain;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        let is_faded_out = self.fade_target <= 0.0 && self.fade_gain <= 0.0;
+        let is_drained = self.is_decoder_done && self.current.is_none() && self.next.is_none();
+        is_faded_out || is_drained
+    }
+
+    fn fetch(&mut self) -> Option<MusicChunk> {
+        match self.receiver.try_recv() {
+            Ok(chunk) => Some(chunk),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.is_decoder_done = true;
+                None
+            },
+        }
+    }
+
+    fn chunk_frames(&self, chunk: &MusicChunk) -> usize {
+        chunk.samples.len() / self.channels
+    }
+
+    fn chunk_frame(&self, chunk: &MusicChunk, index: usize) -> (f32, f32) {
+        let base = index * self.channels;
+        match self.channels {
+            1 => (chunk.samples[base], chunk.samples[base]),
+            _ => (chunk.samples[base], chunk.samples[base + 1]),
+        }
+    }
+
+    /// Makes sure `current` holds the chunk the cursor is in. Returns false on a buffer underrun
+    /// or once the track has ended.
+    fn ensure_current(&mut self) -> bool {
+        loop {
+            if self.current.is_none() {
+                self.current = match self.next.take() {
+                    Some(chunk) => Some(chunk),
+                    None => self.fetch(),
+                };
+            }
+
+            let frames = match self.current.as_ref() {
+                Some(chunk) => self.chunk_frames(chunk),
+                None => return false,
+            };
+            if self.cursor < frames as f64 {
+                return true;
+            }
+            self.cursor -= frames as f64;
+            self.current = None;
+        }
+    }
+
+    fn advance_fade(&mut self) {
+        if self.fade_gain < self.fade_target {
+            self.fade_gain = (self.fade_gain + self.fade_step).min(self.fade_target);
+        } else if self.fade_gain > self.fade_target {
+            self.fade_gain = (self.fade_gain - self.fade_step).max(self.fade_target);
+        }
+    }
+
+    /// Adds the stream into an interleaved stereo buffer, resampling to `output_rate`.
+    pub fn mix_into(&mut self, out: &mut [f32], output_rate: u32) {
+        let step = self.sample_rate as f64 / output_rate as f64;
+
+        for frame in out.chunks_exact_mut(OUTPUT_CHANNELS) {
+            if !self.ensure_current() {
+                break;
+            }
+
+            let (index, frames, start_frame, (left_a, right_a)) = {
+                let chunk = self.current.as_ref().unwrap();
+                let index = self.cursor as usize;
+                (index, self.chunk_frames(chunk), chunk.start_frame, self.chunk_frame(chunk, index))
+            };
+
+            // the next frame may be the first frame of the next chunk
+            let (left_b, right_b) = if index + 1 < frames {
+                self.chunk_frame(self.current.as_ref().unwrap(), index + 1)
+            } else {
+                if self.next.is_none() {
+                    self.next = self.fetch();
+                }
+                match self.next.as_ref() {
+                    Some(next) if self.chunk_frames(next) > 0 => self.chunk_frame(next, 0),
+                    _ => (left_a, right_a),
+                }
+            };
+
+            let fraction = (self.cursor - index as f64) as f32;
+            let gain = self.volume.max(0.0) * self.fade_gain;
+            frame[0] += (left_a + (left_b - left_a) * fraction) * gain;
+            frame[1] += (right_a + (right_b - right_a) * fraction) * gain;
+
+            self.position = start_frame + index as u64;
+            self.cursor += step;
+            self.advance_fade();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::audio::mixer::{VoiceHandle, VoiceId};
+use crate::video::sprite::SpriteId;
+
+/// How quickly a sound gets quieter as it moves away from the listener.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Rolloff {
+    /// Fades out evenly, reaching silence at the max distance
+    Linear,
+    /// Halves the volume every time the distance doubles, like sound in the real world
+    Inverse,
+    /// Loses the same fraction of volume for every reference distance further away, so it
+    /// drops off faster than inverse
+    Exponential,
+}
+
+/// Distances are in the same pixel units as sprite positions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpatialSettings {
+    pub rolloff: Rolloff,
+    /// Sounds closer than this play at full volume
+    pub reference_distance: f32,
+    /// Sounds further away than this are silent
+    pub max_distance: f32,
+    /// Steepness of the inverse and exponential curves, 1.0 is physically accurate
+    pub rolloff_factor: f32,
+}
+
+impl Default for SpatialSettings {
+    fn default() -> Self {
+        Self {
+            rolloff: Rolloff::Inverse,
+            reference_distance: 64.0,
+            max_distance: 1024.0,
+            rolloff_factor: 1.0,
+        }
+    }
+}
+
+impl SpatialSettings {
+    /// Returns the gain for a sound `distance` pixels away from the listener
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        if distance > self.max_distance {
+            return 0.0;
+        }
+
+        let reference = self.reference_distance.max(f32::EPSILON);
+        let distance = distance.clamp(reference, self.max_distance.max(reference));
+
+        let gain = match self.rolloff {
+            Rolloff::Linear => match self.max_distance > reference {
+                true => 1.0 - (distance - reference) / (self.max_distance - reference),
+                false => 1.0,
+            },
+            Rolloff::Inverse => reference / (reference + self.rolloff_factor * (distance - reference)),
+            Rolloff::Exponential => (-self.rolloff_factor * (distance - reference) / reference).exp(),
+        };
+        gain.clamp(0.0, 1.0)
+    }
+}
+
+/// Where a positional sound comes from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Emitter {
+    Point(f32, f32),
+    /// Follows the center of the sprite. If the sprite is removed the sound keeps playing where
+    /// the sprite was last seen.
+    Sprite(SpriteId),
+}
+
+pub(crate) struct SpatialVoice {
+    handle: VoiceHandle,
+    emitter: Emitter,
+    /// Last known position of the emitter, None until an attached sprite has been found
+    position: Option<(f32, f32)>,
+    volume: f32,
+    settings: SpatialSettings,
+}
+
+impl SpatialVoice {
+    pub fn new(handle: VoiceHandle, emitter: Emitter, volume: f32, settings: SpatialSettings) -> Self {
+        let position = match emitter {
+            Emitter::Point(x, y) => Some((x, y)),
+            Emitter::Sprite(_) => None,
+        };
+
+        Self {
+            handle,
+            emitter,
+            position,
+            volume,
+            settings,
+        }
+    }
+
+    pub fn id(&self) -> VoiceId {
+        self.handle.id()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.handle.is_playing()
+    }
+
+    pub fn set_emitter(&mut self, emitter: Emitter) {
+        self.emitter = emitter;
+        if let Emitter::Point(x, y) = emitter {
+            self.position = Some((x, y));
+        }
+    }
+
+    /// Recomputes volume and pan relative to the listener
+    pub fn update<F>(&mut self, listener: (f32, f32), sprite_position: &F)
+    where
+        F: Fn(SpriteId) -> Option<(f32, f32)>,
+    {
+        if let Emitter::Sprite(sprite) = self.emitter {
+            match sprite_position(sprite) {
+                Some(position) => self.position = Some(position),
+                None => if let Some((x, y)) = self.position {
+                    self.emitter = Emitter::Point(x, y);
+                },
+            }
+        }
+
+        let Some((x, y)) = self.position else {
+            self.handle.set_volume(0.0);
+            return;
+        };
+
+        let (dx, dy) = (x - listener.0, y - listener.1);
+        let distance = (dx * dx + dy * dy).sqrt();
+        // sounds straight above or below the listener are centered, directly to the side are
+        // fully panned
+        let pan = match distance > 0.0 {
+            true => dx / distance.max(self.settings.reference_distance),
+            false => 0.0,
+        };
+
+        self.handle
+            .set_volume(self.volume * self.settings.attenuation(distance))
+            .set_pan(pan.clamp(-1.0, 1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(rolloff: Rolloff) -> SpatialSettings {
+        SpatialSettings { rolloff, ..Default::default() }
+    }
+
+    #[test]
+    fn exponential_drops_off_faster_than_inverse() {
+        let inverse = settings(Rolloff::Inverse);
+        let exponential = settings(Rolloff::Exponential);
+
+        assert_eq!(exponential.attenuation(32.0), 1.0);
+        assert_eq!(exponential.attenuation(64.0), 1.0);
+        for distance in [96.0, 128.0, 256.0, 512.0, 1000.0] {
+            assert!(exponential.attenuation(distance) < inverse.attenuation(distance), "{}", distance);
+        }
+        assert!((exponential.attenuation(128.0) - (-1.0_f32).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn silent_past_max_distance() {
+        for rolloff in [Rolloff::Linear, Rolloff::Inverse, Rolloff::Exponential] {
+            assert_eq!(settings(rolloff).attenuation(1025.0), 0.0);
+        }
+        assert_eq!(settings(Rolloff::Linear).attenuation(1024.0), 0.0);
+    }
+}
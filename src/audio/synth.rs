@@ -0,0 +1,289 @@
+use crate::audio::mixer::SoundBuffer;
+
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Waveform {
+    Square,
+    Saw,
+    Sine,
+    Noise,
+}
+
+/// Parameters for a procedurally generated sound effect in the style of sfxr.
+///
+/// Times are in seconds and frequencies in Hz. Slides are in octaves per second, so a slide of
+/// -1.0 halves the frequency every second.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SynthParams {
+    pub waveform: Waveform,
+    pub volume: f32,
+    pub sample_rate: u32,
+
+    pub attack: f32,
+    pub decay: f32,
+    /// Volume held during the sustain phase, from 0.0 to 1.0
+    pub sustain_level: f32,
+    pub sustain_time: f32,
+    pub release: f32,
+
+    pub base_frequency: f32,
+    /// The sound ends early once a downwards slide drops below this frequency
+    pub min_frequency: f32,
+    pub frequency_slide: f32,
+    /// Change of `frequency_slide` per second
+    pub frequency_delta_slide: f32,
+
+    /// Depth of the vibrato as a fraction of the frequency
+    pub vibrato_depth: f32,
+    pub vibrato_speed: f32,
+
+    /// Fraction of each period the square wave spends high, from 0.0 to 1.0
+    pub duty_cycle: f32,
+    /// Change of `duty_cycle` per second
+    pub duty_sweep: f32,
+
+    /// The frequency is multiplied by this once `arpeggio_time` has passed, 1.0 disables it
+    pub arpeggio_multiplier: f32,
+    pub arpeggio_time: f32,
+}
+
+impl Default for SynthParams {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Square,
+            volume: 0.5,
+            sample_rate: 44_100,
+            attack: 0.0,
+            decay: 0.0,
+            sustain_level: 1.0,
+            sustain_time: 0.2,
+            release: 0.2,
+            base_frequency: 440.0,
+            min_frequency: 0.0,
+            frequency_slide: 0.0,
+            frequency_delta_slide: 0.0,
+            vibrato_depth: 0.0,
+            vibrato_speed: 0.0,
+            duty_cycle: 0.5,
+            duty_sweep: 0.0,
+            arpeggio_multiplier: 1.0,
+            arpeggio_time: 0.0,
+        }
+    }
+}
+
+/// xorshift64*, small and deterministic for a given seed on every platform
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift gets stuck on 0 so mix the seed into a non zero state
+        Self { state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in [0.0, 1.0)
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
+
+impl SynthParams {
+    /// Bright coin / item pickup with a jump up in pitch
+    pub fn pickup(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        Self {
+            waveform: Waveform::Square,
+            base_frequency: rng.range(800.0, 1600.0),
+            sustain_time: rng.range(0.03, 0.1),
+            release: rng.range(0.1, 0.3),
+            arpeggio_multiplier: rng.range(1.3, 1.8),
+            arpeggio_time: rng.range(0.04, 0.1),
+            ..Self::default()
+        }
+    }
+
+    /// Fast downwards sweep
+    pub fn laser(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        Self {
+            waveform: if rng.chance(0.5) { Waveform::Square } else { Waveform::Saw },
+            base_frequency: rng.range(600.0, 1800.0),
+            min_frequency: rng.range(80.0, 200.0),
+            frequency_slide: rng.range(-8.0, -3.0),
+            duty_cycle: rng.range(0.2, 0.5),
+            duty_sweep: rng.range(0.0, 1.0),
+            sustain_time: rng.range(0.05, 0.15),
+            release: rng.range(0.05, 0.2),
+            ..Self::default()
+        }
+    }
+
+    /// Low rumbling noise burst with a long tail
+    pub fn explosion(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let has_vibrato = rng.chance(0.5);
+        Self {
+            waveform: Waveform::Noise,
+            base_frequency: rng.range(600.0, 3000.0),
+            frequency_slide: rng.range(-1.5, -0.2),
+            vibrato_depth: if has_vibrato { rng.range(0.1, 0.4) } else { 0.0 },
+            vibrato_speed: if has_vibrato { rng.range(5.0, 20.0) } else { 0.0 },
+            sustain_time: rng.range(0.1, 0.3),
+            release: rng.range(0.3, 0.7),
+            ..Self::default()
+        }
+    }
+
+    /// Upwards sweep
+    pub fn jump(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        Self {
+            waveform: Waveform::Square,
+            base_frequency: rng.range(250.0, 500.0),
+            frequency_slide: rng.range(1.0, 3.0),
+            duty_cycle: rng.range(0.3, 0.6),
+            sustain_time: rng.range(0.1, 0.2),
+            release: rng.range(0.1, 0.2),
+            ..Self::default()
+        }
+    }
+
+    /// Short harsh impact
+    pub fn hit(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        Self {
+            waveform: if rng.chance(0.5) { Waveform::Saw } else { Waveform::Noise },
+            base_frequency: rng.range(200.0, 800.0),
+            frequency_slide: rng.range(-5.0, -2.0),
+            sustain_time: rng.range(0.02, 0.08),
+            release: rng.range(0.1, 0.2),
+            ..Self::default()
+        }
+    }
+
+    /// Every parameter picked at random. The same seed always produces the same sound.
+    pub fn randomize(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let waveform = match rng.next_u64() % 4 {
+            0 => Waveform::Square,
+            1 => Waveform::Saw,
+            2 => Waveform::Sine,
+            _ => Waveform::Noise,
+        };
+        let has_vibrato = rng.chance(0.3);
+        let has_arpeggio = rng.chance(0.3);
+
+        Self {
+            waveform,
+            attack: if rng.chance(0.5) { 0.0 } else { rng.range(0.0, 0.2) },
+            decay: rng.range(0.0, 0.2),
+            sustain_level: rng.range(0.3, 1.0),
+            sustain_time: rng.range(0.05, 0.4),
+            release: rng.range(0.05, 0.5),
+            base_frequency: rng.range(100.0, 2000.0),
+            min_frequency: rng.range(20.0, 100.0),
+            frequency_slide: rng.range(-4.0, 4.0),
+            frequency_delta_slide: rng.range(-2.0, 2.0),
+            vibrato_depth: if has_vibrato { rng.range(0.0, 0.5) } else { 0.0 },
+            vibrato_speed: if has_vibrato { rng.range(1.0, 30.0) } else { 0.0 },
+            duty_cycle: rng.range(0.1, 0.9),
+            duty_sweep: rng.range(-1.0, 1.0),
+            arpeggio_multiplier: if has_arpeggio { rng.range(0.5, 2.0) } else { 1.0 },
+            arpeggio_time: if has_arpeggio { rng.range(0.02, 0.2) } else { 0.0 },
+            ..Self::default()
+        }
+    }
+
+    pub fn duration_seconds(&self) -> f32 {
+        self.attack.max(0.0) + self.decay.max(0.0) + self.sustain_time.max(0.0) + self.release.max(0.0)
+    }
+
+    fn envelope(&self, time: f32) -> f32 {
+        let sustain_level = self.sustain_level.clamp(0.0, 1.0);
+        let decay_start = self.attack.max(0.0);
+        let sustain_start = decay_start + self.decay.max(0.0);
+        let release_start = sustain_start + self.sustain_time.max(0.0);
+
+        if time < decay_start {
+            time / decay_start
+        } else if time < sustain_start {
+            1.0 - (1.0 - sustain_level) * (time - decay_start) / (sustain_start - decay_start)
+        } else if time < release_start {
+            sustain_level
+        } else if self.release > 0.0 {
+            (sustain_level * (1.0 - (time - release_start) / self.release)).max(0.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Renders the sound into a mono buffer. Noise is seeded from the parameters themselves, so
+    /// the same parameters always produce the same samples.
+    pub fn generate(&self) -> SoundBuffer {
+        let sample_rate = self.sample_rate.max(1);
+        let delta = 1.0 / sample_rate as f32;
+        let frames = (self.duration_seconds() * sample_rate as f32) as usize;
+
+        let mut rng = Rng::new(self.base_frequency.to_bits() as u64 ^ ((self.frequency_slide.to_bits() as u64) << 32));
+        let mut samples = Vec::with_capacity(frames);
+        let mut frequency = self.base_frequency;
+        let mut slide = self.frequency_slide;
+        let mut phase = 0.0_f32;
+        let mut noise = rng.range(-1.0, 1.0);
+        let mut is_arpeggio_done = self.arpeggio_multiplier == 1.0 || self.arpeggio_time <= 0.0;
+
+        for frame in 0..frames {
+            let time = frame as f32 * delta;
+
+            if !is_arpeggio_done && time >= self.arpeggio_time {
+                frequency *= self.arpeggio_multiplier;
+                is_arpeggio_done = true;
+            }
+
+            slide += self.frequency_delta_slide * delta;
+            frequency *= 2.0_f32.powf(slide * delta);
+            if self.min_frequency > 0.0 && frequency < self.min_frequency {
+                break;
+            }
+
+            let vibrato = 1.0 + self.vibrato_depth * (2.0 * PI * self.vibrato_speed * time).sin();
+            let duty = (self.duty_cycle + self.duty_sweep * time).clamp(0.05, 0.95);
+
+            phase += frequency * vibrato * delta;
+            if phase >= 1.0 {
+                phase = phase.fract();
+                // noise holds a random value for each period, so the frequency still shapes it
+                noise = rng.range(-1.0, 1.0);
+            }
+
+            let sample = match self.waveform {
+                Waveform::Square => if phase < duty { 1.0 } else { -1.0 },
+                Waveform::Saw => 1.0 - 2.0 * phase,
+                Waveform::Sine => (2.0 * PI * phase).sin(),
+                Waveform::Noise => noise,
+            };
+
+            samples.push((sample * self.envelope(time) * self.volume).clamp(-1.0, 1.0));
+        }
+
+        SoundBuffer::new(samples, 1, sample_rate)
+    }
+}
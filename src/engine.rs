@@ -1,12 +1,33 @@
-use crate::audio::audio_manager::AudioManager;
+use crate::audio::audio_manager::{AudioManager, AudioError, SoundId};
+use crate::audio::backend::AudioBackend;
+use crate::audio::mixer::{VoiceHandle, VoiceId, VoiceSettings, VoiceStealing};
+use crate::audio::music::MusicTrack;
+use crate::audio::bus::BusId;
+use crate::audio::effects::Effect;
+use crate::audio::spatial::{Emitter, SpatialSettings};
+use crate::audio::synth::SynthParams;
 use crate::input::input_manager::{InputManager, Key, Action};
-use crate::video::window::WindowManager;
+use crate::input::input_event::{InputEvent, Modifiers};
+use crate::input::action_map::{ActionMap, ActionMapError};
+use crate::input::recording::InputRecording;
+use crate::input::shortcut::Shortcut;
+use crate::input::gesture::{Gesture, GestureSettings};
+use crate::input::gamepad::{GamepadId, GamepadButton, GamepadAxis, GamepadBackend, GamepadError};
+use crate::video::window::{WindowManager, FrameStats, WindowError};
+use crate::video::window_event::WindowEvent;
+use crate::video::monitor::{MonitorInfo, WindowMode};
+use crate::video::cursor::{CursorMode, CursorShape};
+use crate::video::camera::{Camera2D, CameraId, ViewTransform};
 use crate::video::color::Color;
 use crate::video::sprite::{SpriteSheetId, SpriteSheetError, Sprite, SpriteId};
 use crate::video::shader_manager::{ShaderId, FragmentShader, VertexShader, ShaderError, Attribute, Uniform};
 use crate::video::glfw_window::GlfwWindow;
+use crate::video::framebuffer::Framebuffer;
+use crate::video::headless::HeadlessContext;
 use crate::utility::timer::Timer;
 
+use image::RgbaImage;
+
 use std::collections::HashMap;
 
 pub trait GetId {
@@ -26,19 +47,56 @@ pub struct EngineBuilder {
     should_poll_cursor_pos: bool,
     should_poll_mouse_buttons: bool,
     should_poll_scroll: bool,
+    audio_backend: Option<Box<dyn AudioBackend>>,
+    window_mode: WindowMode,
+    monitor: usize,
+    is_headless: bool,
 }
 
 impl EngineBuilder {
+    /// Panics if the window or headless context can't be created, see `try_init`
     pub fn init(&mut self) -> Engine {
-        Engine::build(
-            self.width, self.height,
-            self.clear_color, 
-            self.window_name.clone().unwrap_or("Window".to_string()).as_str(),
-            self.show_cursor,
-            self.is_bordered, self.is_resizable,
-            self.should_poll_keys, self.should_poll_cursor_pos,
-            self.should_poll_mouse_buttons, self.should_poll_scroll
-        )
+        self.try_init().expect("Failed to create GLFW window.")
+    }
+
+    pub fn try_init(&mut self) -> Result<Engine, WindowError> {
+        let (window, input_manager) = if self.is_headless {
+            let context = HeadlessContext::new(self.clear_color)?;
+            let framebuffer = Framebuffer::new(self.width, self.height)?;
+            (WindowManager::headless(context, framebuffer), InputManager::headless())
+        }
+        else {
+            let window = GlfwWindow::new(
+                self.width, self.height,
+                self.clear_color,
+                self.window_name.clone().unwrap_or("Window".to_string()).as_str(),
+                self.show_cursor,
+                self.is_bordered, self.is_resizable,
+                self.should_poll_keys, self.should_poll_cursor_pos,
+                self.should_poll_mouse_buttons, self.should_poll_scroll,
+            )?;
+            (WindowManager::new(window.window), InputManager::new(window.glfw, window.events))
+        };
+        let mut engine = Engine::build(window, input_manager, self.audio_backend.take());
+
+        if !self.is_headless && (self.window_mode != WindowMode::Windowed || self.monitor != 0) {
+            if let Err(err) = engine.set_window_mode(self.window_mode, self.monitor) {
+                eprintln!("Error: Failed to set starting window mode:\n\t{}", err);
+            }
+        }
+        Ok(engine)
+    }
+
+    /// Renders into a `width` by `height` offscreen framebuffer without a window, for tests and
+    /// CI machines with neither a display server nor a GPU. The context comes from EGL's
+    /// surfaceless platform, Mesa's software rasterizer is enough. Frames are read with
+    /// `Engine::read_pixels`. Window settings are ignored and there is no keyboard or mouse
+    /// input, gamepads are simulated.
+    pub fn headless(&mut self, width: u32, height: u32) -> &mut Self {
+        self.is_headless = true;
+        self.width = width;
+        self.height = height;
+        self
     }
 
     pub fn hide_cursor(&mut self) -> &mut Self {
@@ -87,6 +145,23 @@ impl EngineBuilder {
         self.window_name = Some(name.to_string());
         self
     }
+
+    /// Replaces the default output device, e.g. with an `OfflineBackend` on machines without audio.
+    pub fn set_audio_backend(&mut self, backend: Box<dyn AudioBackend>) -> &mut Self {
+        self.audio_backend = Some(backend);
+        self
+    }
+
+    pub fn set_window_mode(&mut self, mode: WindowMode) -> &mut Self {
+        self.window_mode = mode;
+        self
+    }
+
+    /// Monitor to open the window on, see `Engine::get_monitors`. Defaults to the primary monitor.
+    pub fn set_monitor(&mut self, index: usize) -> &mut Self {
+        self.monitor = index;
+        self
+    }
 }
 
 #[allow(dead_code)]
@@ -125,40 +200,27 @@ impl Engine {
             should_poll_scroll: false,
             should_poll_mouse_buttons: false,
             should_poll_cursor_pos: false,
+            audio_backend: None,
+            window_mode: WindowMode::Windowed,
+            monitor: 0,
+            is_headless: false,
         }
     }
 
     fn build(
-        width: u32,
-        height: u32,
-        clear_color: Color,
-        window_name: &str,
-        show_cursor: bool,
-        is_bordered: bool,
-        is_resizable: bool,
-        should_poll_keys: bool,
-        should_poll_cursor_pos: bool,
-        should_poll_mouse_buttons: bool,
-        should_poll_scroll: bool,
+        window: WindowManager,
+        input_manager: InputManager,
+        audio_backend: Option<Box<dyn AudioBackend>>,
     ) -> Self {
-        let window = GlfwWindow::new(
-            width,
-            height,
-            clear_color,
-            window_name,
-            show_cursor,
-            is_bordered,
-            is_resizable,
-            should_poll_keys,
-            should_poll_cursor_pos,
-            should_poll_mouse_buttons,
-            should_poll_scroll,
-        );
+        let audio_manager = match audio_backend {
+            Some(backend) => AudioManager::with_backend(backend),
+            None => AudioManager::new(),
+        };
 
         Self {
-            audio_manager: AudioManager::new(),
-            input_manager: InputManager::new(window.glfw, window.events),
-            window: WindowManager::new(window.window),
+            audio_manager,
+            input_manager,
+            window,
             global_timer: Timer::new(),
         }
     }
@@ -189,6 +251,44 @@ impl Engine {
         self.window.get_dimensions()
     }
 
+    /// Adds a camera, cameras are drawn in the order they were added. Without any camera the
+    /// window shows world coordinates 0,0 to `get_window_dimensions` one to one.
+    pub fn add_camera(&mut self, camera: Camera2D) -> CameraId {
+        self.window.add_camera(camera)
+    }
+
+    pub fn get_camera(&mut self, id: CameraId) -> Option<&mut Camera2D> {
+        self.window.get_camera(id)
+    }
+
+    pub fn remove_camera(&mut self, id: CameraId) {
+        self.window.remove_camera(id);
+    }
+
+    /// The view being drawn, for use in `Attribute` and `Uniform` callbacks
+    pub fn get_current_view(&self) -> ViewTransform {
+        self.window.get_current_view()
+    }
+
+    /// Converts window coordinates, e.g. from `cursor_position`, to world coordinates through
+    /// the topmost camera under that point
+    pub fn screen_to_world(&self, x: f64, y: f64) -> (f32, f32) {
+        self.window.screen_to_world(x, y)
+    }
+
+    /// Window coordinates a world point is drawn at by the first camera
+    pub fn world_to_screen(&self, x: f32, y: f32) -> (f64, f64) {
+        self.window.world_to_screen(x, y)
+    }
+
+    pub fn camera_screen_to_world(&self, camera: CameraId, x: f64, y: f64) -> Option<(f32, f32)> {
+        self.window.camera_screen_to_world(camera, x, y)
+    }
+
+    pub fn camera_world_to_screen(&self, camera: CameraId, x: f32, y: f32) -> Option<(f64, f64)> {
+        self.window.camera_world_to_screen(camera, x, y)
+    }
+
     pub fn add_sprite_sheet(
         &mut self,
         path: &str,
@@ -221,6 +321,22 @@ impl Engine {
         self.window.get_all_sprites()
     }
 
+    /// Copies the last drawn frame with rows from top to bottom, e.g. to compare it against a
+    /// reference image with `count_mismatched_pixels`
+    pub fn read_pixels(&self) -> RgbaImage {
+        self.window.read_pixels()
+    }
+
+    /// True if the engine was built with `EngineBuilder::headless`
+    pub fn is_headless(&self) -> bool {
+        self.window.is_headless()
+    }
+
+    /// Sprites drawn and culled and draw calls made in the last frame
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.window.get_frame_stats()
+    }
+
     pub fn add_sprite(
         &mut self, sprite_sheet: SpriteSheetId, 
         sprite_index: usize,
@@ -239,14 +355,112 @@ impl Engine {
         self.window.remove_sprite(sprite_id);
     }
 
+    /// Switches between a window and borderless fullscreen
     pub fn toggle_fullscreen(&mut self) {
         self.window.toggle_fullscreen();
     }
 
+    /// Going back to `WindowMode::Windowed` restores the position and size the window had
+    /// before going fullscreen
+    pub fn set_window_mode(&mut self, mode: WindowMode, monitor: usize) -> Result<(), WindowError> {
+        self.window.set_window_mode(mode, monitor)
+    }
+
+    pub fn get_window_mode(&self) -> WindowMode {
+        self.window.get_window_mode()
+    }
+
+    /// Connected monitors with their video modes, the primary monitor comes first
+    pub fn get_monitors(&mut self) -> Vec<MonitorInfo> {
+        self.window.get_monitors()
+    }
+
+    /// Index of the monitor the window is on
+    pub fn get_current_monitor(&mut self) -> usize {
+        self.window.get_current_monitor()
+    }
+
     pub fn set_window_size(&mut self, width: i32, height: i32) {
         self.window.set_window_size(width, height);
     }
 
+    pub fn set_window_title(&mut self, title: &str) {
+        self.window.set_title(title);
+    }
+
+    /// Loads the icon shown in the title bar and task bar from a PNG, the platform scales it as needed
+    pub fn set_window_icon(&mut self, png_path: &str) -> Result<(), WindowError> {
+        self.window.set_icon(png_path)
+    }
+
+    /// Moves the top left corner of the content area to `x`, `y` in screen coordinates
+    pub fn set_window_position(&mut self, x: i32, y: i32) {
+        self.window.set_position(x, y);
+    }
+
+    pub fn get_window_position(&self) -> (i32, i32) {
+        self.window.get_position()
+    }
+
+    /// Smallest and largest size the user can resize the window to as (width, height), None for no limit
+    pub fn set_size_limits(&mut self, min: Option<(u32, u32)>, max: Option<(u32, u32)>) {
+        self.window.set_size_limits(min, max);
+    }
+
+    /// Keeps the window at `ratio`, e.g. Some((16, 9)), while the user resizes it. None removes the lock.
+    pub fn set_aspect_ratio_lock(&mut self, ratio: Option<(u32, u32)>) {
+        self.window.set_aspect_ratio_lock(ratio);
+    }
+
+    /// Opacity of the whole window from 0.0 to 1.0, not supported on every platform
+    pub fn set_window_opacity(&mut self, opacity: f32) {
+        self.window.set_opacity(opacity);
+    }
+
+    pub fn get_window_opacity(&self) -> f32 {
+        self.window.get_opacity()
+    }
+
+    /// Flashes the task bar entry or bounces the dock icon until the window is focused
+    pub fn request_attention(&mut self) {
+        self.window.request_attention();
+    }
+
+    pub fn get_cursor_mode(&self) -> CursorMode {
+        self.window.get_cursor_mode()
+    }
+
+    /// `CursorMode::Captured` locks the cursor to the window for mouse look, read the movement
+    /// with `cursor_delta`
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        self.window.set_cursor_mode(mode);
+    }
+
+    /// Uses one of the cursors of the operating system, e.g. a hand over buttons
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        self.window.set_cursor_shape(shape);
+    }
+
+    /// Uses a sprite as the cursor at its size in the sheet. `hotspot` is the pixel that
+    /// clicks, counted from the top left corner of the sprite.
+    pub fn set_cursor_image(
+        &mut self,
+        sprite_sheet: SpriteSheetId,
+        sprite_index: usize,
+        hotspot: (u32, u32),
+    ) -> Result<(), WindowError> {
+        self.window.set_cursor_image(sprite_sheet, sprite_index, hotspot)
+    }
+
+    pub fn set_cursor_image_from_png(&mut self, png_path: &str, hotspot: (u32, u32)) -> Result<(), WindowError> {
+        self.window.set_cursor_image_from_png(png_path, hotspot)
+    }
+
+    /// Goes back to the default arrow cursor after `set_cursor_shape` or `set_cursor_image`
+    pub fn reset_cursor(&mut self) {
+        self.window.reset_cursor();
+    }
+
     pub fn toggle_show_fps(&mut self) {
         self.window.toggle_show_fps();
     }
@@ -263,10 +477,257 @@ impl Engine {
         return self.input_manager.read_events();
     }
 
+    /// Cursor position in window pixels with the origin at the top left, None until the cursor
+    /// has moved over the window. Requires `EngineBuilder::poll_cursor`.
+    pub fn cursor_position(&mut self) -> Option<(f64, f64)> {
+        self.input_manager.cursor_position()
+    }
+
+    /// Cursor position in world coordinates, the space `Sprite::get_position` uses
+    pub fn cursor_sprite_position(&mut self) -> Option<(f32, f32)> {
+        let (x, y) = self.input_manager.cursor_position()?;
+        Some(self.window.screen_to_world(x, y))
+    }
+
+    /// How far the cursor moved in window pixels since the last frame
+    pub fn cursor_delta(&mut self) -> (f64, f64) {
+        self.input_manager.cursor_delta()
+    }
+
+    pub fn is_cursor_in_window(&mut self) -> bool {
+        self.input_manager.is_cursor_in_window()
+    }
+
+    /// True on the frame the cursor moved into the window
+    pub fn cursor_entered(&mut self) -> bool {
+        self.input_manager.cursor_entered()
+    }
+
+    /// True on the frame the cursor moved out of the window
+    pub fn cursor_left(&mut self) -> bool {
+        self.input_manager.cursor_left()
+    }
+
+    /// Accumulated (horizontal, vertical) scroll this frame, positive y is scrolling up.
+    /// Requires `EngineBuilder::poll_mouse_buttons`.
+    pub fn scroll_delta(&mut self) -> (f64, f64) {
+        self.input_manager.scroll_delta()
+    }
+
+    /// Every key, mouse button, cursor and scroll event of this frame in the order they
+    /// happened, including presses and releases that both happened within the same frame.
+    pub fn drain_input_events(&mut self) -> Vec<InputEvent> {
+        self.input_manager.drain_events()
+    }
+
+    /// Modifier keys currently held down
+    pub fn get_modifiers(&mut self) -> Modifiers {
+        self.input_manager.modifiers()
+    }
+
+    /// What `key` prints in the current keyboard layout, for showing key bindings to players
+    pub fn get_key_name(&self, key: Key) -> Option<String> {
+        self.input_manager.key_name(key)
+    }
+
+    pub fn is_shortcut_pressed(&mut self, shortcut: &Shortcut) -> bool {
+        self.input_manager.is_shortcut_pressed(shortcut)
+    }
+
+    /// Also true while the OS repeats the key, for shortcuts like undo that should repeat
+    pub fn is_shortcut_repeated(&mut self, shortcut: &Shortcut) -> bool {
+        self.input_manager.is_shortcut_repeated(shortcut)
+    }
+
+    /// Clicks, double clicks, drags and long presses completed this frame with positions in
+    /// world coordinates
+    pub fn get_gestures(&mut self) -> Vec<Gesture> {
+        let window = &self.window;
+        self.input_manager.gestures().into_iter()
+            .map(|gesture| gesture.map_positions(|(x, y)| window.screen_to_world(x as f64, y as f64)))
+            .collect()
+    }
+
+    pub fn get_gesture_settings(&self) -> GestureSettings {
+        self.input_manager.get_gesture_settings()
+    }
+
+    pub fn set_gesture_settings(&mut self, settings: GestureSettings) {
+        self.input_manager.set_gesture_settings(settings);
+    }
+
+    /// Text typed this frame as UTF-8, after any IME composition. Requires
+    /// `EngineBuilder::poll_keyboard`.
+    pub fn text_input(&mut self) -> String {
+        self.input_manager.text_input()
+    }
+
+    /// True if the OS repeated `key` this frame because it's being held down
+    pub fn is_key_repeated(&mut self, key: Key) -> bool {
+        self.input_manager.is_key_repeated(key)
+    }
+
+    /// None if the clipboard is empty or doesn't contain text
+    pub fn clipboard_text(&self) -> Option<String> {
+        self.window.get_clipboard_text()
+    }
+
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        self.window.set_clipboard_text(text);
+    }
+
+    /// Records keyboard and mouse input frame by frame until `stop_input_recording`
+    pub fn start_input_recording(&mut self) {
+        self.input_manager.start_recording();
+    }
+
+    pub fn stop_input_recording(&mut self) -> Option<InputRecording> {
+        self.input_manager.stop_recording()
+    }
+
+    pub fn is_recording_input(&self) -> bool {
+        self.input_manager.is_recording()
+    }
+
+    /// Feeds `recording` into `get_key_events` and every other keyboard and mouse query in
+    /// place of the real devices, live gamepad events are dropped meanwhile. Event timestamps
+    /// are seconds since the recording started. Use a fixed frame rate with `set_fps` so the
+    /// game steps the same way it did while recording.
+    pub fn start_input_playback(&mut self, recording: InputRecording) {
+        self.input_manager.start_playback(recording);
+    }
+
+    pub fn stop_input_playback(&mut self) {
+        self.input_manager.stop_playback();
+    }
+
+    /// False once the recording has been played to the end
+    pub fn is_playing_back_input(&self) -> bool {
+        self.input_manager.is_playing_back()
+    }
+
+    /// Replaces the GLFW joysticks, e.g. with a `SimulatedGamepadBackend`
+    pub fn set_gamepad_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        self.input_manager.get_gamepads().set_backend(backend);
+    }
+
+    /// Connected gamepads sorted by id, `InputEvent::GamepadConnected` and
+    /// `InputEvent::GamepadDisconnected` report changes
+    pub fn get_connected_gamepads(&mut self) -> Vec<GamepadId> {
+        self.input_manager.get_gamepads().get_connected()
+    }
+
+    pub fn is_gamepad_connected(&mut self, id: GamepadId) -> bool {
+        self.input_manager.get_gamepads().is_connected(id)
+    }
+
+    pub fn get_gamepad_name(&mut self, id: GamepadId) -> Option<String> {
+        self.input_manager.get_gamepads().get_name(id).map(|name| name.to_string())
+    }
+
+    pub fn get_gamepad_button(&mut self, id: GamepadId, button: GamepadButton) -> Action {
+        self.input_manager.get_gamepads().get_button_state(id, button)
+    }
+
+    /// Sticks range from -1.0 to 1.0 with positive y pointing down, triggers from 0.0 to 1.0
+    pub fn get_gamepad_axis(&mut self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.input_manager.get_gamepads().get_axis(id, axis)
+    }
+
+    pub fn get_gamepad_left_stick(&mut self, id: GamepadId) -> (f32, f32) {
+        self.input_manager.get_gamepads().get_left_stick(id)
+    }
+
+    pub fn get_gamepad_right_stick(&mut self, id: GamepadId) -> (f32, f32) {
+        self.input_manager.get_gamepads().get_right_stick(id)
+    }
+
+    /// Defaults to 0.15 for the sticks and 0.05 for the triggers
+    pub fn set_gamepad_dead_zone(&mut self, stick: f32, trigger: f32) {
+        self.input_manager.get_gamepads().set_dead_zone(stick, trigger);
+    }
+
+    /// (stick, trigger) dead zones
+    pub fn get_gamepad_dead_zone(&mut self) -> (f32, f32) {
+        self.input_manager.get_gamepads().get_dead_zone()
+    }
+
+    /// Adds SDL_GameControllerDB mapping lines for controllers GLFW doesn't know
+    pub fn add_gamepad_mappings(&mut self, mappings: &str) -> Result<(), GamepadError> {
+        self.input_manager.get_gamepads().add_mappings(mappings)
+    }
+
+    /// Loads a gamecontrollerdb.txt file
+    pub fn load_gamepad_mappings(&mut self, path: &str) -> Result<(), GamepadError> {
+        self.input_manager.get_gamepads().load_mappings(path)
+    }
+
+    /// Bindings used by `is_action_pressed`, `is_action_held`, `is_action_released` and
+    /// `axis_value`
+    pub fn get_action_map(&mut self) -> &mut ActionMap {
+        self.input_manager.get_action_map()
+    }
+
+    pub fn set_action_map(&mut self, action_map: ActionMap) {
+        self.input_manager.set_action_map(action_map);
+    }
+
+    pub fn load_action_map(&mut self, path: &str) -> Result<(), ActionMapError> {
+        self.input_manager.set_action_map(ActionMap::load(path)?);
+        Ok(())
+    }
+
+    pub fn save_action_map(&mut self, path: &str) -> Result<(), ActionMapError> {
+        self.input_manager.get_action_map().save(path)
+    }
+
+    /// True on the frame any key bound to `action` was pressed
+    pub fn is_action_pressed(&mut self, action: &str) -> bool {
+        self.input_manager.is_action_pressed(action)
+    }
+
+    /// True while any key bound to `action` is down
+    pub fn is_action_held(&mut self, action: &str) -> bool {
+        self.input_manager.is_action_held(action)
+    }
+
+    pub fn is_action_released(&mut self, action: &str) -> bool {
+        self.input_manager.is_action_released(action)
+    }
+
+    /// Value of `axis` from -1.0 to 1.0
+    pub fn axis_value(&mut self, axis: &str) -> f32 {
+        self.input_manager.axis_value(axis)
+    }
+
     pub fn is_running(&self) -> bool {
         return self.window.is_running();
     }
 
+    /// Resizes, focus changes, close requests, dropped files and other window events of this
+    /// frame in the order they happened
+    pub fn drain_window_events(&mut self) -> Vec<WindowEvent> {
+        self.input_manager.drain_window_events()
+    }
+
+    /// While enabled, closing the window only sends `WindowEvent::CloseRequested` and the game
+    /// keeps running until it calls `stop`, e.g. after asking to save unsaved progress
+    pub fn set_close_intercept(&mut self, should_intercept: bool) {
+        self.window.set_close_intercept(should_intercept);
+    }
+
+    pub fn is_window_focused(&self) -> bool {
+        self.window.is_focused()
+    }
+
+    pub fn is_window_minimized(&self) -> bool {
+        self.window.is_minimized()
+    }
+
+    pub fn is_window_maximized(&self) -> bool {
+        self.window.is_maximized()
+    }
+
     pub fn toggle_border(&mut self) {
         self.window.toggle_border();
     }
@@ -280,6 +741,8 @@ impl Engine {
     }
 
     pub fn draw_frame(&mut self) {
+        self.update_audio_positions();
+
         unsafe {
             /* NOTE In order for shader attribute and uniform callbacks to have access to relevant data
              * inside the engine we need to pass a reference to the engine to this funcion. Rust
@@ -290,6 +753,152 @@ impl Engine {
              */
             self.window.draw_frame(self as *const Engine);
         }
+        self.input_manager.end_frame();
+    }
+
+    /// Loads a WAV file into memory so it can be played with `play_sound`
+    pub fn load_sound(&mut self, path: &str) -> Result<SoundId, AudioError> {
+        self.audio_manager.load_sound(path)
+    }
+
+    /// Generates a retro sound effect, e.g. `engine.synthesize_sound(&SynthParams::pickup(seed))`
+    pub fn synthesize_sound(&mut self, params: &SynthParams) -> SoundId {
+        self.audio_manager.synthesize_sound(params)
+    }
+
+    pub fn save_sound_wav(&self, sound_id: SoundId, path: &str) -> Result<(), AudioError> {
+        self.audio_manager.save_sound_wav(sound_id, path)
+    }
+
+    pub fn play_sound(&mut self, sound_id: SoundId) -> Result<VoiceHandle, AudioError> {
+        self.audio_manager.play_sound(sound_id)
+    }
+
+    pub fn play_sound_with(&mut self, sound_id: SoundId, settings: VoiceSettings) -> Result<VoiceHandle, AudioError> {
+        self.audio_manager.play_sound_with(sound_id, settings)
+    }
+
+    /// Plays a sound at a point in sprite coordinates, it gets quieter and pans with its
+    /// distance from the listener
+    pub fn play_sound_at(&mut self, sound_id: SoundId, x: f32, y: f32) -> Result<VoiceHandle, AudioError> {
+        self.audio_manager.play_sound_at(sound_id, x, y)
+    }
+
+    /// Plays a positional sound that follows the sprite
+    pub fn play_sound_attached(&mut self, sound_id: SoundId, sprite_id: SpriteId) -> Result<VoiceHandle, AudioError> {
+        let handle = self.audio_manager.play_sound_attached(sound_id, sprite_id)?;
+        self.update_audio_positions();
+        Ok(handle)
+    }
+
+    pub fn play_spatial(
+        &mut self,
+        sound_id: SoundId,
+        emitter: Emitter,
+        settings: VoiceSettings,
+        spatial: SpatialSettings,
+    ) -> Result<VoiceHandle, AudioError> {
+        let handle = self.audio_manager.play_spatial(sound_id, emitter, settings, spatial)?;
+        self.update_audio_positions();
+        Ok(handle)
+    }
+
+    pub fn set_emitter(&mut self, voice: VoiceId, emitter: Emitter) {
+        self.audio_manager.set_emitter(voice, emitter);
+    }
+
+    pub fn set_spatial_settings(&mut self, settings: SpatialSettings) {
+        self.audio_manager.set_spatial_settings(settings);
+    }
+
+    pub fn set_listener_position(&mut self, x: f32, y: f32) {
+        self.audio_manager.set_listener_position(x, y);
+    }
+
+    /// Makes the listener follow a sprite, None leaves it at its last position
+    pub fn attach_listener(&mut self, sprite_id: Option<SpriteId>) {
+        self.audio_manager.attach_listener(sprite_id);
+        self.update_audio_positions();
+    }
+
+    fn update_audio_positions(&mut self) {
+        let sprites = self.window.get_all_sprites();
+        self.audio_manager.update_spatial(|sprite_id| {
+            let sprite = sprites.get(&sprite_id)?;
+            let (x, y) = sprite.get_position();
+            Some((
+                x as f32 + sprite.get_width() as f32 / 2.0,
+                y as f32 + sprite.get_height() as f32 / 2.0,
+            ))
+        });
+    }
+
+    /// Limits how many sounds can play at once, `stealing` decides which sound gets cut off
+    pub fn set_voice_limit(&mut self, limit: usize, stealing: VoiceStealing) {
+        self.audio_manager.set_voice_limit(limit, stealing);
+    }
+
+    pub fn remove_sound(&mut self, sound_id: SoundId) {
+        self.audio_manager.remove_sound(sound_id);
+    }
+
+    pub fn stop_all_sounds(&mut self) {
+        self.audio_manager.stop_all();
+    }
+
+    /// Streams a music track from disk, replacing the current music
+    pub fn play_music(&mut self, track: &MusicTrack) -> Result<(), AudioError> {
+        self.audio_manager.play_music(track)
+    }
+
+    /// Blends from the current music into `track` over `duration` seconds
+    pub fn crossfade_to(&mut self, track: &MusicTrack, duration: f32) -> Result<(), AudioError> {
+        self.audio_manager.crossfade_to(track, duration)
+    }
+
+    pub fn stop_music(&mut self, fade_out: f32) {
+        self.audio_manager.stop_music(fade_out);
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.audio_manager.set_music_volume(volume);
+    }
+
+    pub fn music_position(&self) -> Option<f32> {
+        self.audio_manager.music_position()
+    }
+
+    /// Adds a named bus that is summed into `BusId::MASTER`
+    pub fn add_bus(&mut self, name: &str) -> BusId {
+        self.audio_manager.add_bus(name)
+    }
+
+    pub fn get_bus_id(&self, name: &str) -> Option<BusId> {
+        self.audio_manager.get_bus_id(name)
+    }
+
+    pub fn get_bus_gain(&self, bus: BusId) -> Option<f32> {
+        self.audio_manager.get_bus_gain(bus)
+    }
+
+    pub fn set_bus_gain(&mut self, bus: BusId, gain: f32) {
+        self.audio_manager.set_bus_gain(bus, gain);
+    }
+
+    pub fn set_bus_muted(&mut self, bus: BusId, muted: bool) {
+        self.audio_manager.set_bus_muted(bus, muted);
+    }
+
+    pub fn set_bus_solo(&mut self, bus: BusId, soloed: bool) {
+        self.audio_manager.set_bus_solo(bus, soloed);
+    }
+
+    pub fn add_bus_effect(&mut self, bus: BusId, effect: Box<dyn Effect>) {
+        self.audio_manager.add_bus_effect(bus, effect);
+    }
+
+    pub fn clear_bus_effects(&mut self, bus: BusId) {
+        self.audio_manager.clear_bus_effects(bus);
     }
 
     pub fn get_uv_from_sprite_sheet(&self, sprite_sheet: SpriteSheetId, index: usize) -> Option<(f32, f32, f32, f32)> {
@@ -0,0 +1,408 @@
+use crate::input::input_manager::{Key, Action};
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ActionMapError {
+    IOError(std::io::Error),
+    ParseError(String),
+}
+
+impl std::fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionMapError::IOError(e)    => write!(f, "IOError: {}", e),
+            ActionMapError::ParseError(e) => write!(f, "ParseError: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for ActionMapError {
+    fn from(value: std::io::Error) -> Self {
+        ActionMapError::IOError(value)
+    }
+}
+
+/// Named actions and axes bound to keys and mouse buttons, so games can check "jump" instead of
+/// `Key::Space` and let players rebind their controls.
+///
+/// Bindings are saved as a small TOML file:
+/// ```toml
+/// [actions]
+/// jump = ["Space", "MouseLeft"]
+///
+/// [axes]
+/// move_x = [["A", "D"], ["ArrowLeft", "ArrowRight"]]
+/// ```
+/// Every axis binding is a `[negative, positive]` pair.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionMap {
+    actions: HashMap<String, Vec<Key>>,
+    axes: HashMap<String, Vec<(Key, Key)>>,
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `key` to the keys that trigger `action`, creating the action if needed
+    pub fn bind_action(&mut self, action: &str, key: Key) -> &mut Self {
+        let keys = self.actions.entry(action.to_string()).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        self
+    }
+
+    pub fn unbind_action(&mut self, action: &str, key: Key) -> &mut Self {
+        if let Some(keys) = self.actions.get_mut(action) {
+            keys.retain(|bound| *bound != key);
+        }
+        self
+    }
+
+    /// Replaces every binding of `action` with `keys`, used when a player remaps a control
+    pub fn set_action_bindings(&mut self, action: &str, keys: &[Key]) -> &mut Self {
+        self.actions.insert(action.to_string(), keys.to_vec());
+        self
+    }
+
+    pub fn get_action_bindings(&self, action: &str) -> &[Key] {
+        self.actions.get(action).map(|keys| keys.as_slice()).unwrap_or(&[])
+    }
+
+    /// Adds a pair of keys to `axis`, `negative` pushes the value towards -1.0 and `positive`
+    /// towards 1.0
+    pub fn bind_axis(&mut self, axis: &str, negative: Key, positive: Key) -> &mut Self {
+        let pairs = self.axes.entry(axis.to_string()).or_default();
+        if !pairs.contains(&(negative, positive)) {
+            pairs.push((negative, positive));
+        }
+        self
+    }
+
+    pub fn set_axis_bindings(&mut self, axis: &str, pairs: &[(Key, Key)]) -> &mut Self {
+        self.axes.insert(axis.to_string(), pairs.to_vec());
+        self
+    }
+
+    pub fn get_axis_bindings(&self, axis: &str) -> &[(Key, Key)] {
+        self.axes.get(axis).map(|pairs| pairs.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn remove_action(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn remove_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    /// Names of every action, sorted so they can be listed in an options menu
+    pub fn get_actions(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.actions.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    pub fn get_axes(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.axes.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// True on the frame any key bound to `action` was pressed
+    pub fn is_action_pressed(&self, action: &str, key_states: &HashMap<Key, Action>) -> bool {
+        self.get_action_bindings(action).iter()
+            .any(|key| key_states.get(key) == Some(&Action::Pressed))
+    }
+
+    /// True while any key bound to `action` is down, including the frame it was pressed
+    pub fn is_action_held(&self, action: &str, key_states: &HashMap<Key, Action>) -> bool {
+        self.get_action_bindings(action).iter()
+            .any(|key| is_down(key_states.get(key)))
+    }
+
+    /// True on the frame the last key holding `action` down was released
+    pub fn is_action_released(&self, action: &str, key_states: &HashMap<Key, Action>) -> bool {
+        let keys = self.get_action_bindings(action);
+        keys.iter().any(|key| key_states.get(key) == Some(&Action::Released))
+            && !keys.iter().any(|key| is_down(key_states.get(key)))
+    }
+
+    /// Value of `axis` from -1.0 to 1.0. Opposing keys cancel each other out.
+    pub fn axis_value(&self, axis: &str, key_states: &HashMap<Key, Action>) -> f32 {
+        let value: f32 = self.get_axis_bindings(axis).iter()
+            .map(|(negative, positive)| {
+                let negative = if is_down(key_states.get(negative)) { 1.0 } else { 0.0 };
+                let positive = if is_down(key_states.get(positive)) { 1.0 } else { 0.0 };
+                positive - negative
+            })
+            .sum();
+        value.clamp(-1.0, 1.0)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), ActionMapError> {
+        std::fs::write(Path::new(path), self.to_toml())?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<ActionMap, ActionMapError> {
+        let text = std::fs::read_to_string(Path::new(path))?;
+        ActionMap::from_toml(&text)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut text = String::from("[actions]\n");
+        for name in self.get_actions() {
+            let keys: Vec<String> = self.get_action_bindings(name).iter()
+                .map(|key| format!("\"{}\"", key))
+                .collect();
+            let _ = writeln!(text, "{} = [{}]", toml_key(name), keys.join(", "));
+        }
+
+        text.push_str("\n[axes]\n");
+        for name in self.get_axes() {
+            let pairs: Vec<String> = self.get_axis_bindings(name).iter()
+                .map(|(negative, positive)| format!("[\"{}\", \"{}\"]", negative, positive))
+                .collect();
+            let _ = writeln!(text, "{} = [{}]", toml_key(name), pairs.join(", "));
+        }
+        text
+    }
+
+    /// Parses the subset of TOML written by `to_toml`. Comments and blank lines are ignored.
+    pub fn from_toml(text: &str) -> Result<ActionMap, ActionMapError> {
+        let mut map = ActionMap::new();
+        let mut section = "";
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| ActionMapError::ParseError(format!("line {}: {}", line_number, message));
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && !line.contains('=') {
+                section = match line {
+                    "[actions]" => "actions",
+                    "[axes]" => "axes",
+                    _ => return Err(error(format!("unknown section {}", line))),
+                };
+                continue;
+            }
+
+            let (name, value) = find_unquoted(line, '=')
+                .map(|index| (&line[..index], &line[index + 1..]))
+                .ok_or_else(|| error("expected name = value".to_string()))?;
+            let name = parse_name(name.trim()).map_err(error)?;
+            let value = Value::parse(value.trim()).map_err(error)?;
+
+            match section {
+                "actions" => {
+                    let keys = value.into_keys().map_err(error)?;
+                    map.set_action_bindings(&name, &keys);
+                },
+                "axes" => {
+                    let mut pairs = Vec::new();
+                    for pair in value.into_list().map_err(error)? {
+                        match pair.into_keys().map_err(error)?.as_slice() {
+                            [negative, positive] => pairs.push((*negative, *positive)),
+                            _ => return Err(error("axis bindings must be [negative, positive] pairs".to_string())),
+                        }
+                    }
+                    map.set_axis_bindings(&name, &pairs);
+                },
+                _ => return Err(error("binding outside of [actions] or [axes]".to_string())),
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+fn is_down(action: Option<&Action>) -> bool {
+    matches!(action, Some(Action::Pressed) | Some(Action::Held))
+}
+
+fn is_bare_key(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn toml_key(name: &str) -> String {
+    match is_bare_key(name) {
+        true => name.to_string(),
+        false => {
+            let mut key = String::from('"');
+            for c in name.chars() {
+                match c {
+                    '"' => key.push_str("\\\""),
+                    '\\' => key.push_str("\\\\"),
+                    '\n' => key.push_str("\\n"),
+                    '\r' => key.push_str("\\r"),
+                    '\t' => key.push_str("\\t"),
+                    c => key.push(c),
+                }
+            }
+            key.push('"');
+            key
+        },
+    }
+}
+
+fn strip_comment(line: &str) -> &str {
+    match find_unquoted(line, '#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+/// Byte index of the first `target` outside of a string, skipping escaped characters
+fn find_unquoted(line: &str, target: char) -> Option<usize> {
+    let mut is_quoted = false;
+    let mut is_escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' if is_quoted => is_escaped = true,
+            '"' => is_quoted = !is_quoted,
+            c if c == target && !is_quoted => return Some(index),
+            _ => {},
+        }
+    }
+    None
+}
+
+fn parse_name(name: &str) -> Result<String, String> {
+    if is_bare_key(name) {
+        return Ok(name.to_string());
+    }
+    let mut chars = name.chars().peekable();
+    match parse_string(&mut chars)? {
+        name if chars.peek().is_none() => Ok(name),
+        _ => Err(format!("invalid name {}", name)),
+    }
+}
+
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err("expected a string".to_string());
+    }
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some(c @ ('"' | '\\')) => string.push(c),
+                Some('n') => string.push('\n'),
+                Some('r') => string.push('\r'),
+                Some('t') => string.push('\t'),
+                _ => return Err("unsupported escape in string".to_string()),
+            },
+            Some(c) => string.push(c),
+            None => return Err("unterminated string".to_string()),
+        }
+    }
+}
+
+/// A string or a (nested) array of strings
+enum Value {
+    String(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn parse(text: &str) -> Result<Value, String> {
+        let mut chars = text.chars().peekable();
+        let value = Value::parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        match chars.peek() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Value, String> {
+        skip_whitespace(chars);
+        match chars.peek() {
+            Some('"') => Ok(Value::String(parse_string(chars)?)),
+            Some('[') => {
+                chars.next();
+                let mut values = Vec::new();
+                loop {
+                    skip_whitespace(chars);
+                    if chars.peek() == Some(&']') {
+                        chars.next();
+                        return Ok(Value::List(values));
+                    }
+                    values.push(Value::parse_value(chars)?);
+                    skip_whitespace(chars);
+                    match chars.next() {
+                        Some(',') => {},
+                        Some(']') => return Ok(Value::List(values)),
+                        _ => return Err("expected ',' or ']' in array".to_string()),
+                    }
+                }
+            },
+            _ => Err("expected a string or an array".to_string()),
+        }
+    }
+
+    fn into_list(self) -> Result<Vec<Value>, String> {
+        match self {
+            Value::List(values) => Ok(values),
+            Value::String(_) => Err("expected an array".to_string()),
+        }
+    }
+
+    fn into_keys(self) -> Result<Vec<Key>, String> {
+        self.into_list()?.into_iter()
+            .map(|value| match value {
+                Value::String(name) => name.parse::<Key>(),
+                Value::List(_) => Err("expected a key name".to_string()),
+            })
+            .collect()
+    }
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_with_special_characters_round_trip() {
+        let mut map = ActionMap::new();
+        let names = ["jump", "say \"hi\" # not a comment", "a = b", "back\\slash", "two\nlines\tand tab"];
+        for (name, key) in names.iter().zip([Key::Space, Key::A, Key::B, Key::C, Key::D]) {
+            map.set_action_bindings(name, &[key]);
+            map.set_axis_bindings(name, &[(Key::ArrowLeft, key)]);
+        }
+
+        let text = map.to_toml();
+        assert_eq!(text.lines().count(), 2 * names.len() + 3, "{}", text);
+
+        let loaded = ActionMap::from_toml(&text).unwrap();
+        for (name, key) in names.iter().zip([Key::Space, Key::A, Key::B, Key::C, Key::D]) {
+            assert_eq!(loaded.get_action_bindings(name), [key], "{}", name);
+            assert_eq!(loaded.get_axis_bindings(name), [(Key::ArrowLeft, key)], "{}", name);
+        }
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        let text = "# bindings\n[actions]\njump = [\"Space\"] # main\n\"#fire\" = [\"A\"]\n";
+        let map = ActionMap::from_toml(text).unwrap();
+        assert_eq!(map.get_action_bindings("jump"), [Key::Space]);
+        assert_eq!(map.get_action_bindings("#fire"), [Key::A]);
+    }
+}
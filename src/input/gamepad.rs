@@ -0,0 +1,521 @@
+use crate::input::input_event::InputEvent;
+use crate::input::input_manager::Action;
+
+use glfw::Glfw;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+pub enum GamepadError {
+    IOError(std::io::Error),
+    InvalidMapping(String),
+}
+
+impl std::fmt::Display for GamepadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GamepadError::IOError(e)        => write!(f, "IOError: {}", e),
+            GamepadError::InvalidMapping(e) => write!(f, "InvalidMapping: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for GamepadError {
+    fn from(value: std::io::Error) -> Self {
+        GamepadError::IOError(value)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
+pub struct GamepadId {
+    id: u32,
+}
+
+impl GamepadId {
+    /// The joystick slot, GLFW supports slots 0 to 15
+    pub fn from_index(index: u32) -> Self {
+        GamepadId { id: index }
+    }
+
+    pub fn get_index(&self) -> u32 {
+        self.id
+    }
+}
+
+/// Buttons of the standard gamepad layout, named after the Xbox controller
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GamepadButton {
+    A, B, X, Y,
+    LeftBumper, RightBumper,
+    Back, Start, Guide,
+    LeftThumb, RightThumb,
+    DpadUp, DpadRight, DpadDown, DpadLeft,
+}
+
+impl GamepadButton {
+    /// Every button in the same order as GLFW reports them
+    pub const ALL: [GamepadButton; 15] = [
+        GamepadButton::A, GamepadButton::B, GamepadButton::X, GamepadButton::Y,
+        GamepadButton::LeftBumper, GamepadButton::RightBumper,
+        GamepadButton::Back, GamepadButton::Start, GamepadButton::Guide,
+        GamepadButton::LeftThumb, GamepadButton::RightThumb,
+        GamepadButton::DpadUp, GamepadButton::DpadRight, GamepadButton::DpadDown, GamepadButton::DpadLeft,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
+pub enum GamepadAxis {
+    LeftX, LeftY, RightX, RightY,
+    LeftTrigger, RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX, GamepadAxis::LeftY, GamepadAxis::RightX, GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Raw state of a gamepad at one point in time. Sticks range from -1.0 to 1.0 with positive y
+/// pointing down, triggers range from 0.0 at rest to 1.0 fully pressed.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct GamepadSnapshot {
+    pub buttons: [bool; 15],
+    pub axes: [f32; 6],
+}
+
+impl GamepadSnapshot {
+    pub fn is_down(&self, button: GamepadButton) -> bool {
+        self.buttons[button.index()]
+    }
+
+    pub fn get_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis.index()]
+    }
+}
+
+/// Where gamepad state comes from. `GlfwGamepadBackend` reads real devices and
+/// `SimulatedGamepadBackend` can be driven by hand.
+pub trait GamepadBackend {
+    /// Every connected gamepad and its current state, called once per frame
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadSnapshot)>;
+
+    fn get_name(&self, id: GamepadId) -> Option<String>;
+
+    /// Adds SDL_GameControllerDB mapping lines, returns false if they couldn't be parsed
+    fn update_mappings(&mut self, mappings: &str) -> bool;
+}
+
+/// Reads joysticks through GLFW. Only joysticks with a gamepad mapping are reported, GLFW
+/// ships with the SDL_GameControllerDB mappings of the most common controllers.
+pub struct GlfwGamepadBackend {
+    glfw: Glfw,
+}
+
+impl GlfwGamepadBackend {
+    pub fn new(glfw: Glfw) -> Self {
+        Self { glfw }
+    }
+}
+
+impl GamepadBackend for GlfwGamepadBackend {
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadSnapshot)> {
+        let mut gamepads = Vec::new();
+        for index in 0..16 {
+            let Some(joystick_id) = glfw::JoystickId::from_i32(index) else {
+                continue;
+            };
+            let Some(state) = self.glfw.get_joystick(joystick_id).get_gamepad_state() else {
+                continue;
+            };
+
+            let mut snapshot = GamepadSnapshot::default();
+            for (button, down) in snapshot.buttons.iter_mut().enumerate() {
+                if let Some(button) = glfw::GamepadButton::from_i32(button as i32) {
+                    *down = state.get_button_state(button) == glfw::Action::Press;
+                }
+            }
+            for (axis, value) in snapshot.axes.iter_mut().enumerate() {
+                if let Some(glfw_axis) = glfw::GamepadAxis::from_i32(axis as i32) {
+                    *value = state.get_axis(glfw_axis);
+                }
+            }
+            // GLFW reports triggers from -1.0 at rest to 1.0
+            for axis in [GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger] {
+                snapshot.axes[axis.index()] = (snapshot.axes[axis.index()] + 1.0) / 2.0;
+            }
+
+            gamepads.push((GamepadId::from_index(index as u32), snapshot));
+        }
+        gamepads
+    }
+
+    fn get_name(&self, id: GamepadId) -> Option<String> {
+        let joystick = self.glfw.get_joystick(glfw::JoystickId::from_i32(id.get_index() as i32)?);
+        joystick.get_gamepad_name().or_else(|| joystick.get_name())
+    }
+
+    fn update_mappings(&mut self, mappings: &str) -> bool {
+        self.glfw.update_gamepad_mappings(mappings)
+    }
+}
+
+/// A gamepad that is controlled from code, for tests and for replaying input. It's cheap to
+/// clone and every clone controls the same devices, so keep one after handing a clone to
+/// `Engine::set_gamepad_backend`.
+#[derive(Clone, Default)]
+pub struct SimulatedGamepadBackend {
+    gamepads: Arc<Mutex<HashMap<GamepadId, (String, GamepadSnapshot)>>>,
+}
+
+impl SimulatedGamepadBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, id: GamepadId, name: &str) {
+        if let Ok(mut gamepads) = self.gamepads.lock() {
+            gamepads.insert(id, (name.to_string(), GamepadSnapshot::default()));
+        }
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        if let Ok(mut gamepads) = self.gamepads.lock() {
+            gamepads.remove(&id);
+        }
+    }
+
+    pub fn set_button(&self, id: GamepadId, button: GamepadButton, down: bool) {
+        if let Some((_, snapshot)) = self.gamepads.lock().ok().as_deref_mut().and_then(|gamepads| gamepads.get_mut(&id)) {
+            snapshot.buttons[button.index()] = down;
+        }
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        if let Some((_, snapshot)) = self.gamepads.lock().ok().as_deref_mut().and_then(|gamepads| gamepads.get_mut(&id)) {
+            snapshot.axes[axis.index()] = value;
+        }
+    }
+}
+
+impl GamepadBackend for SimulatedGamepadBackend {
+    fn poll(&mut self) -> Vec<(GamepadId, GamepadSnapshot)> {
+        match self.gamepads.lock() {
+            Ok(gamepads) => gamepads.iter().map(|(id, (_, snapshot))| (*id, *snapshot)).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn get_name(&self, id: GamepadId) -> Option<String> {
+        self.gamepads.lock().ok()?.get(&id).map(|(name, _)| name.clone())
+    }
+
+    fn update_mappings(&mut self, mappings: &str) -> bool {
+        validate_mappings(mappings).is_ok()
+    }
+}
+
+/// Checks that every line looks like `guid,name,binding:value,...`. Comments start with #.
+fn validate_mappings(mappings: &str) -> Result<(), GamepadError> {
+    for (index, line) in mappings.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split(',');
+        let guid = fields.next().unwrap_or("");
+        let has_name = fields.next().is_some_and(|name| !name.is_empty());
+        let has_bindings = fields.filter(|field| !field.is_empty()).all(|field| field.contains(':'));
+
+        if guid.len() != 32 || !guid.chars().all(|c| c.is_ascii_hexdigit()) || !has_name || !has_bindings {
+            return Err(GamepadError::InvalidMapping(format!("line {}: {}", index + 1, line)));
+        }
+    }
+    Ok(())
+}
+
+struct Gamepad {
+    name: String,
+    buttons: [Action; 15],
+    axes: [f32; 6],
+}
+
+/// Turns the raw snapshots of a `GamepadBackend` into per-frame button states with the same
+/// Pressed, Held and Released semantics as keys, and reports gamepads being plugged in and out.
+pub struct GamepadManager {
+    backend: Box<dyn GamepadBackend>,
+    gamepads: HashMap<GamepadId, Gamepad>,
+    /// Gamepads of a replaced backend, reported as disconnected on the next update
+    old_gamepads: Vec<(GamepadId, Gamepad)>,
+    stick_dead_zone: f32,
+    trigger_dead_zone: f32,
+}
+
+impl GamepadManager {
+    pub fn new(backend: Box<dyn GamepadBackend>) -> Self {
+        Self {
+            backend,
+            gamepads: HashMap::new(),
+            old_gamepads: Vec::new(),
+            stick_dead_zone: 0.15,
+            trigger_dead_zone: 0.05,
+        }
+    }
+
+    /// Swaps the device source, every gamepad of the old backend is reported as disconnected
+    /// on the next update
+    pub fn set_backend(&mut self, backend: Box<dyn GamepadBackend>) {
+        self.backend = backend;
+        self.old_gamepads.extend(self.gamepads.drain());
+    }
+
+    /// Stick movement within `stick` of the center and trigger presses below `trigger` are
+    /// ignored, the rest of the range is rescaled so values still start at 0.0
+    pub fn set_dead_zone(&mut self, stick: f32, trigger: f32) {
+        self.stick_dead_zone = stick.clamp(0.0, 0.99);
+        self.trigger_dead_zone = trigger.clamp(0.0, 0.99);
+    }
+
+    pub fn get_dead_zone(&self) -> (f32, f32) {
+        (self.stick_dead_zone, self.trigger_dead_zone)
+    }
+
+    /// Adds mappings in the SDL_GameControllerDB format, one controller per line
+    pub fn add_mappings(&mut self, mappings: &str) -> Result<(), GamepadError> {
+        validate_mappings(mappings)?;
+        match self.backend.update_mappings(mappings) {
+            true => Ok(()),
+            false => Err(GamepadError::InvalidMapping("the backend rejected the mappings".to_string())),
+        }
+    }
+
+    /// Loads a gamecontrollerdb.txt file
+    pub fn load_mappings(&mut self, path: &str) -> Result<(), GamepadError> {
+        let mappings = std::fs::read_to_string(path)?;
+        self.add_mappings(&mappings)
+    }
+
+    /// Polls the backend and advances every button by one frame. Returns connect, disconnect
+    /// and button events in a stable order. Buttons still down on a disconnected gamepad get a
+    /// Released event before the disconnect.
+    pub fn update(&mut self, timestamp: f64) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        let mut polled = self.backend.poll();
+        polled.sort_by_key(|(id, _)| *id);
+
+        let mut disconnected = std::mem::take(&mut self.old_gamepads);
+        let unplugged: Vec<GamepadId> = self.gamepads.keys()
+            .filter(|id| !polled.iter().any(|(polled_id, _)| polled_id == *id))
+            .copied()
+            .collect();
+        for id in unplugged {
+            if let Some(gamepad) = self.gamepads.remove(&id) {
+                disconnected.push((id, gamepad));
+            }
+        }
+        disconnected.sort_by_key(|(id, _)| *id);
+        for (id, gamepad) in disconnected {
+            for button in GamepadButton::ALL {
+                if matches!(gamepad.buttons[button.index()], Action::Pressed | Action::Held) {
+                    events.push(InputEvent::GamepadButton { id, button, action: Action::Released, timestamp });
+                }
+            }
+            events.push(InputEvent::GamepadDisconnected { id, timestamp });
+        }
+
+        for (id, snapshot) in polled {
+            if !self.gamepads.contains_key(&id) {
+                let name = self.backend.get_name(id).unwrap_or_default();
+                events.push(InputEvent::GamepadConnected { id, name: name.clone(), timestamp });
+                self.gamepads.insert(id, Gamepad {
+                    name,
+                    buttons: [Action::None; 15],
+                    axes: [0.0; 6],
+                });
+            }
+            let Some(gamepad) = self.gamepads.get_mut(&id) else {
+                continue;
+            };
+
+            gamepad.axes = snapshot.axes;
+            for button in GamepadButton::ALL {
+                let state = &mut gamepad.buttons[button.index()];
+                *state = match (*state, snapshot.is_down(button)) {
+                    (Action::None | Action::Released, true) => Action::Pressed,
+                    (Action::Pressed | Action::Held, true) => Action::Held,
+                    (Action::Pressed | Action::Held, false) => Action::Released,
+                    (Action::None | Action::Released, false) => Action::None,
+                };
+                if matches!(*state, Action::Pressed | Action::Released) {
+                    events.push(InputEvent::GamepadButton { id, button, action: *state, timestamp });
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Sorted by id
+    pub fn get_connected(&self) -> Vec<GamepadId> {
+        let mut ids: Vec<GamepadId> = self.gamepads.keys().copied().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn is_connected(&self, id: GamepadId) -> bool {
+        self.gamepads.contains_key(&id)
+    }
+
+    pub fn get_name(&self, id: GamepadId) -> Option<&str> {
+        self.gamepads.get(&id).map(|gamepad| gamepad.name.as_str())
+    }
+
+    /// `Action::None` if the button isn't down or the gamepad isn't connected
+    pub fn get_button_state(&self, id: GamepadId, button: GamepadButton) -> Action {
+        self.gamepads.get(&id)
+            .map(|gamepad| gamepad.buttons[button.index()])
+            .unwrap_or(Action::None)
+    }
+
+    /// The axis value after the dead zone is applied. Sticks use a radial dead zone, so moving
+    /// one axis of a stick also depends on the other.
+    pub fn get_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        match axis {
+            GamepadAxis::LeftX => self.get_left_stick(id).0,
+            GamepadAxis::LeftY => self.get_left_stick(id).1,
+            GamepadAxis::RightX => self.get_right_stick(id).0,
+            GamepadAxis::RightY => self.get_right_stick(id).1,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                let value = self.get_raw_axis(id, axis).clamp(0.0, 1.0);
+                match value < self.trigger_dead_zone {
+                    true => 0.0,
+                    false => (value - self.trigger_dead_zone) / (1.0 - self.trigger_dead_zone),
+                }
+            },
+        }
+    }
+
+    pub fn get_raw_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepads.get(&id).map(|gamepad| gamepad.axes[axis.index()]).unwrap_or(0.0)
+    }
+
+    pub fn get_left_stick(&self, id: GamepadId) -> (f32, f32) {
+        self.get_stick(id, GamepadAxis::LeftX, GamepadAxis::LeftY)
+    }
+
+    pub fn get_right_stick(&self, id: GamepadId) -> (f32, f32) {
+        self.get_stick(id, GamepadAxis::RightX, GamepadAxis::RightY)
+    }
+
+    fn get_stick(&self, id: GamepadId, x_axis: GamepadAxis, y_axis: GamepadAxis) -> (f32, f32) {
+        let (x, y) = (self.get_raw_axis(id, x_axis), self.get_raw_axis(id, y_axis));
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude <= self.stick_dead_zone {
+            return (0.0, 0.0);
+        }
+
+        let scaled = ((magnitude - self.stick_dead_zone) / (1.0 - self.stick_dead_zone)).min(1.0);
+        (x / magnitude * scaled, y / magnitude * scaled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button_event(id: GamepadId, button: GamepadButton, action: Action) -> InputEvent {
+        InputEvent::GamepadButton { id, button, action, timestamp: 0.0 }
+    }
+
+    #[test]
+    fn simulated_gamepad_lifecycle() {
+        let device = SimulatedGamepadBackend::new();
+        let mut manager = GamepadManager::new(Box::new(device.clone()));
+        let id = GamepadId::from_index(0);
+        assert!(manager.update(0.0).is_empty());
+
+        device.connect(id, "Pad");
+        assert_eq!(manager.update(0.0), [InputEvent::GamepadConnected { id, name: "Pad".to_string(), timestamp: 0.0 }]);
+        assert_eq!(manager.get_connected(), [id]);
+        assert_eq!(manager.get_name(id), Some("Pad"));
+
+        device.set_button(id, GamepadButton::A, true);
+        assert_eq!(manager.update(0.0), [button_event(id, GamepadButton::A, Action::Pressed)]);
+        assert!(manager.update(0.0).is_empty());
+        assert_eq!(manager.get_button_state(id, GamepadButton::A), Action::Held);
+
+        device.set_button(id, GamepadButton::A, false);
+        assert_eq!(manager.update(0.0), [button_event(id, GamepadButton::A, Action::Released)]);
+        manager.update(0.0);
+        assert_eq!(manager.get_button_state(id, GamepadButton::A), Action::None);
+
+        // held through the disconnect
+        device.set_button(id, GamepadButton::B, true);
+        manager.update(0.0);
+        device.disconnect(id);
+        assert_eq!(manager.update(0.0), [
+            button_event(id, GamepadButton::B, Action::Released),
+            InputEvent::GamepadDisconnected { id, timestamp: 0.0 },
+        ]);
+        assert!(!manager.is_connected(id));
+        assert_eq!(manager.get_button_state(id, GamepadButton::B), Action::None);
+    }
+
+    #[test]
+    fn dead_zones_are_applied_and_rescaled() {
+        let device = SimulatedGamepadBackend::new();
+        let mut manager = GamepadManager::new(Box::new(device.clone()));
+        let id = GamepadId::from_index(1);
+        device.connect(id, "Pad");
+        manager.set_dead_zone(0.2, 0.1);
+
+        device.set_axis(id, GamepadAxis::LeftX, 0.1);
+        device.set_axis(id, GamepadAxis::LeftY, -0.1);
+        device.set_axis(id, GamepadAxis::RightTrigger, 0.05);
+        manager.update(0.0);
+        assert_eq!(manager.get_left_stick(id), (0.0, 0.0));
+        assert_eq!(manager.get_axis(id, GamepadAxis::RightTrigger), 0.0);
+        assert_eq!(manager.get_raw_axis(id, GamepadAxis::LeftX), 0.1);
+
+        device.set_axis(id, GamepadAxis::LeftX, 0.6);
+        device.set_axis(id, GamepadAxis::LeftY, 0.0);
+        device.set_axis(id, GamepadAxis::RightTrigger, 1.0);
+        manager.update(0.0);
+        assert!((manager.get_axis(id, GamepadAxis::LeftX) - 0.5).abs() < 1e-6);
+        assert_eq!(manager.get_axis(id, GamepadAxis::LeftY), 0.0);
+        assert!((manager.get_axis(id, GamepadAxis::RightTrigger) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn replacing_the_backend_disconnects_every_gamepad() {
+        let old_device = SimulatedGamepadBackend::new();
+        let mut manager = GamepadManager::new(Box::new(old_device.clone()));
+        let (first, second) = (GamepadId::from_index(0), GamepadId::from_index(1));
+        old_device.connect(first, "Old");
+        old_device.connect(second, "Old");
+        old_device.set_button(second, GamepadButton::Start, true);
+        manager.update(0.0);
+
+        // the new backend reuses an id, it still has to show up as a new gamepad
+        let new_device = SimulatedGamepadBackend::new();
+        new_device.connect(first, "New");
+        manager.set_backend(Box::new(new_device));
+        assert_eq!(manager.update(0.0), [
+            InputEvent::GamepadDisconnected { id: first, timestamp: 0.0 },
+            button_event(second, GamepadButton::Start, Action::Released),
+            InputEvent::GamepadDisconnected { id: second, timestamp: 0.0 },
+            InputEvent::GamepadConnected { id: first, name: "New".to_string(), timestamp: 0.0 },
+        ]);
+        assert_eq!(manager.get_connected(), [first]);
+        assert_eq!(manager.get_name(first), Some("New"));
+    }
+}
@@ -0,0 +1,313 @@
+use crate::input::input_event::{InputEvent, InputSource};
+use crate::input::input_manager::{Key, Action};
+
+use std::collections::HashMap;
+
+/// A pointer gesture. `button` is the mouse button that made it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Gesture {
+    /// Pressed and released without moving further than the drag threshold
+    Click { button: Key, position: (f32, f32) },
+    /// A second click close to the first one, sent right after its `Click`
+    DoubleClick { button: Key, position: (f32, f32) },
+    /// Held without moving for the long press time, no `Click` follows on release
+    LongPress { button: Key, position: (f32, f32) },
+    /// The cursor moved further than the drag threshold while a button was held
+    DragStart { button: Key, start: (f32, f32) },
+    DragMove { button: Key, start: (f32, f32), previous: (f32, f32), position: (f32, f32) },
+    DragEnd { button: Key, start: (f32, f32), position: (f32, f32) },
+}
+
+impl Gesture {
+    pub fn get_button(&self) -> Key {
+        match *self {
+            Gesture::Click { button, .. }
+                | Gesture::DoubleClick { button, .. }
+                | Gesture::LongPress { button, .. }
+                | Gesture::DragStart { button, .. }
+                | Gesture::DragMove { button, .. }
+                | Gesture::DragEnd { button, .. } => button,
+        }
+    }
+
+    /// Converts every position of the gesture, e.g. from window to sprite coordinates
+    pub fn map_positions<F>(self, map: F) -> Gesture
+    where
+        F: Fn((f32, f32)) -> (f32, f32),
+    {
+        match self {
+            Gesture::Click { button, position } => Gesture::Click { button, position: map(position) },
+            Gesture::DoubleClick { button, position } => Gesture::DoubleClick { button, position: map(position) },
+            Gesture::LongPress { button, position } => Gesture::LongPress { button, position: map(position) },
+            Gesture::DragStart { button, start } => Gesture::DragStart { button, start: map(start) },
+            Gesture::DragMove { button, start, previous, position } => Gesture::DragMove {
+                button,
+                start: map(start),
+                previous: map(previous),
+                position: map(position),
+            },
+            Gesture::DragEnd { button, start, position } => Gesture::DragEnd {
+                button,
+                start: map(start),
+                position: map(position),
+            },
+        }
+    }
+}
+
+/// Times are in seconds and distances in window pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GestureSettings {
+    /// Longest time between two clicks that still counts as a double click
+    pub double_click_time: f64,
+    /// Furthest the second click of a double click can be from the first one
+    pub double_click_distance: f32,
+    /// How far the cursor has to move with a button held before it becomes a drag
+    pub drag_threshold: f32,
+    pub long_press_time: f64,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        Self {
+            double_click_time: 0.3,
+            double_click_distance: 6.0,
+            drag_threshold: 4.0,
+            long_press_time: 0.5,
+        }
+    }
+}
+
+struct Press {
+    start: (f32, f32),
+    start_time: f64,
+    is_dragging: bool,
+    is_long_pressed: bool,
+}
+
+struct LastClick {
+    button: Key,
+    time: f64,
+    position: (f32, f32),
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Turns mouse events into gestures. It only looks at `InputEvent`s, so it can be fed a made
+/// up event stream without a window.
+pub struct GestureRecognizer {
+    settings: GestureSettings,
+    cursor: (f32, f32),
+    presses: HashMap<Key, Press>,
+    last_click: Option<LastClick>,
+}
+
+impl GestureRecognizer {
+    pub fn new(settings: GestureSettings) -> Self {
+        Self {
+            settings,
+            cursor: (0.0, 0.0),
+            presses: HashMap::new(),
+            last_click: None,
+        }
+    }
+
+    pub fn get_settings(&self) -> GestureSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: GestureSettings) {
+        self.settings = settings;
+    }
+
+    /// Feeds one event in and returns the gestures it completed
+    pub fn process(&mut self, event: &InputEvent) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+
+        match *event {
+            InputEvent::CursorMoved { x, y, .. } => {
+                let previous = self.cursor;
+                self.cursor = (x as f32, y as f32);
+
+                let mut buttons: Vec<Key> = self.presses.keys().copied().collect();
+                buttons.sort_by_key(|button| button.to_string());
+                for button in buttons {
+                    let Some(press) = self.presses.get_mut(&button) else {
+                        continue;
+                    };
+                    if !press.is_dragging && distance(press.start, self.cursor) > self.settings.drag_threshold {
+                        press.is_dragging = true;
+                        gestures.push(Gesture::DragStart { button, start: press.start });
+                    }
+                    if press.is_dragging {
+                        gestures.push(Gesture::DragMove { button, start: press.start, previous, position: self.cursor });
+                    }
+                }
+            },
+            InputEvent::Key { key, action: Action::Pressed, source: InputSource::Mouse, timestamp, .. } if is_button(key) => {
+                self.presses.insert(key, Press {
+                    start: self.cursor,
+                    start_time: timestamp,
+                    is_dragging: false,
+                    is_long_pressed: false,
+                });
+            },
+            InputEvent::Key { key, action: Action::Released, source: InputSource::Mouse, timestamp, .. } => {
+                let Some(press) = self.presses.remove(&key) else {
+                    return gestures;
+                };
+
+                if press.is_dragging {
+                    gestures.push(Gesture::DragEnd { button: key, start: press.start, position: self.cursor });
+                }
+                else if !press.is_long_pressed {
+                    gestures.push(Gesture::Click { button: key, position: press.start });
+
+                    let is_double_click = self.last_click.as_ref().is_some_and(|last| {
+                        last.button == key
+                            && timestamp - last.time <= self.settings.double_click_time
+                            && distance(last.position, press.start) <= self.settings.double_click_distance
+                    });
+                    if is_double_click {
+                        gestures.push(Gesture::DoubleClick { button: key, position: press.start });
+                        // a third click starts a new double click instead of completing another
+                        self.last_click = None;
+                    }
+                    else {
+                        self.last_click = Some(LastClick { button: key, time: timestamp, position: press.start });
+                    }
+                }
+            },
+            _ => {},
+        }
+
+        gestures
+    }
+
+    /// Checks for long presses at `time`, call this once per frame after the events of the frame
+    pub fn update(&mut self, time: f64) -> Vec<Gesture> {
+        let mut gestures: Vec<Gesture> = Vec::new();
+        for (button, press) in self.presses.iter_mut() {
+            if !press.is_dragging && !press.is_long_pressed && time - press.start_time >= self.settings.long_press_time {
+                press.is_long_pressed = true;
+                gestures.push(Gesture::LongPress { button: *button, position: press.start });
+            }
+        }
+        gestures.sort_by_key(|gesture| gesture.get_button().to_string());
+        gestures
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureSettings::default())
+    }
+}
+
+fn is_button(key: Key) -> bool {
+    !matches!(key, Key::MouseScrollUp | Key::MouseScrollDown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::input_event::Modifiers;
+
+    fn button(action: Action, timestamp: f64) -> InputEvent {
+        InputEvent::Key { key: Key::MouseLeft, action, modifiers: Modifiers::NONE, source: InputSource::Mouse, timestamp }
+    }
+
+    fn move_to(x: f64, y: f64) -> InputEvent {
+        InputEvent::CursorMoved { x, y, timestamp: 0.0 }
+    }
+
+    /// Feeds every event and returns all gestures in order
+    fn feed(recognizer: &mut GestureRecognizer, events: &[InputEvent]) -> Vec<Gesture> {
+        events.iter().flat_map(|event| recognizer.process(event)).collect()
+    }
+
+    fn click_at(x: f64, y: f64, time: f64) -> [InputEvent; 3] {
+        [move_to(x, y), button(Action::Pressed, time), button(Action::Released, time + 0.05)]
+    }
+
+    #[test]
+    fn click() {
+        let mut recognizer = GestureRecognizer::default();
+        let gestures = feed(&mut recognizer, &click_at(10.0, 20.0, 0.0));
+        assert_eq!(gestures, [Gesture::Click { button: Key::MouseLeft, position: (10.0, 20.0) }]);
+    }
+
+    #[test]
+    fn small_movement_is_still_a_click() {
+        let mut recognizer = GestureRecognizer::default();
+        let gestures = feed(&mut recognizer, &[
+            move_to(0.0, 0.0),
+            button(Action::Pressed, 0.0),
+            move_to(3.0, 0.0),
+            button(Action::Released, 0.1),
+        ]);
+        assert_eq!(gestures, [Gesture::Click { button: Key::MouseLeft, position: (0.0, 0.0) }]);
+    }
+
+    #[test]
+    fn double_click_timing_and_distance() {
+        let double_click = Gesture::DoubleClick { button: Key::MouseLeft, position: (12.0, 20.0) };
+
+        let mut recognizer = GestureRecognizer::default();
+        feed(&mut recognizer, &click_at(10.0, 20.0, 0.0));
+        let gestures = feed(&mut recognizer, &click_at(12.0, 20.0, 0.25));
+        assert_eq!(gestures, [Gesture::Click { button: Key::MouseLeft, position: (12.0, 20.0) }, double_click]);
+
+        // a third click starts over
+        let gestures = feed(&mut recognizer, &click_at(12.0, 20.0, 0.5));
+        assert!(!gestures.contains(&Gesture::DoubleClick { button: Key::MouseLeft, position: (12.0, 20.0) }));
+
+        let mut too_slow = GestureRecognizer::default();
+        feed(&mut too_slow, &click_at(10.0, 20.0, 0.0));
+        assert_eq!(feed(&mut too_slow, &click_at(12.0, 20.0, 0.4)).len(), 1);
+
+        let mut too_far = GestureRecognizer::default();
+        feed(&mut too_far, &click_at(10.0, 20.0, 0.0));
+        assert_eq!(feed(&mut too_far, &click_at(17.0, 20.0, 0.1)).len(), 1);
+    }
+
+    #[test]
+    fn drag_start_move_and_end() {
+        let mut recognizer = GestureRecognizer::default();
+        let button = Key::MouseLeft;
+        let gestures = feed(&mut recognizer, &[
+            move_to(0.0, 0.0),
+            self::button(Action::Pressed, 0.0),
+            move_to(4.0, 0.0),
+            move_to(5.0, 0.0),
+            move_to(9.0, 2.0),
+            self::button(Action::Released, 0.2),
+        ]);
+        assert_eq!(gestures, [
+            Gesture::DragStart { button, start: (0.0, 0.0) },
+            Gesture::DragMove { button, start: (0.0, 0.0), previous: (4.0, 0.0), position: (5.0, 0.0) },
+            Gesture::DragMove { button, start: (0.0, 0.0), previous: (5.0, 0.0), position: (9.0, 2.0) },
+            Gesture::DragEnd { button, start: (0.0, 0.0), position: (9.0, 2.0) },
+        ]);
+    }
+
+    #[test]
+    fn long_press_has_no_click() {
+        let mut recognizer = GestureRecognizer::default();
+        feed(&mut recognizer, &[move_to(5.0, 5.0), button(Action::Pressed, 1.0)]);
+
+        assert!(recognizer.update(1.4).is_empty());
+        assert_eq!(recognizer.update(1.5), [Gesture::LongPress { button: Key::MouseLeft, position: (5.0, 5.0) }]);
+        assert!(recognizer.update(2.0).is_empty());
+        assert!(feed(&mut recognizer, &[button(Action::Released, 2.0)]).is_empty());
+    }
+
+    #[test]
+    fn dragging_prevents_long_press() {
+        let mut recognizer = GestureRecognizer::default();
+        feed(&mut recognizer, &[move_to(0.0, 0.0), button(Action::Pressed, 0.0), move_to(10.0, 0.0)]);
+        assert!(recognizer.update(1.0).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use crate::input::input_manager::{Key, Action};
+use crate::input::gamepad::{GamepadId, GamepadButton};
+
+/// Modifier keys held down when a key or mouse button event happened.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub super_key: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+impl From<glfw::Modifiers> for Modifiers {
+    fn from(value: glfw::Modifiers) -> Self {
+        Self {
+            shift: value.contains(glfw::Modifiers::Shift),
+            control: value.contains(glfw::Modifiers::Control),
+            alt: value.contains(glfw::Modifiers::Alt),
+            super_key: value.contains(glfw::Modifiers::Super),
+            caps_lock: value.contains(glfw::Modifiers::CapsLock),
+            num_lock: value.contains(glfw::Modifiers::NumLock),
+        }
+    }
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        shift: false, control: false, alt: false, super_key: false, caps_lock: false, num_lock: false,
+    };
+}
+
+/// The device an event came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum InputSource {
+    Keyboard,
+    Mouse,
+    Gamepad,
+}
+
+/// A single input event in the order it was received. `timestamp` is the GLFW time in seconds
+/// at which the event was polled, or the seconds since the recording started while an
+/// `InputRecording` is replayed.
+#[derive(Clone, PartialEq, Debug)]
+pub enum InputEvent {
+    /// A key or mouse button changed state
+    Key {
+        key: Key,
+        action: Action,
+        modifiers: Modifiers,
+        source: InputSource,
+        timestamp: f64,
+    },
+    /// The OS repeated a held key, useful for Backspace and arrow keys in text fields
+    KeyRepeat {
+        key: Key,
+        modifiers: Modifiers,
+        timestamp: f64,
+    },
+    /// Unicode text typed by the user. IME composition happens in the OS, so this is the
+    /// committed text after it has been composed.
+    Text {
+        text: String,
+        timestamp: f64,
+    },
+    /// Cursor moved to (x, y) in window pixels, origin at the top left
+    CursorMoved {
+        x: f64,
+        y: f64,
+        timestamp: f64,
+    },
+    /// The cursor entered (true) or left (false) the window
+    CursorEntered {
+        entered: bool,
+        timestamp: f64,
+    },
+    Scroll {
+        x: f64,
+        y: f64,
+        timestamp: f64,
+    },
+    GamepadConnected {
+        id: GamepadId,
+        name: String,
+        timestamp: f64,
+    },
+    GamepadDisconnected {
+        id: GamepadId,
+        timestamp: f64,
+    },
+    /// A gamepad button was pressed or released
+    GamepadButton {
+        id: GamepadId,
+        button: GamepadButton,
+        action: Action,
+        timestamp: f64,
+    },
+}
+
+impl InputEvent {
+    pub fn timestamp(&self) -> f64 {
+        match self {
+            InputEvent::Key { timestamp, .. }            => *timestamp,
+            InputEvent::KeyRepeat { timestamp, .. }      => *timestamp,
+            InputEvent::Text { timestamp, .. }           => *timestamp,
+            InputEvent::CursorMoved { timestamp, .. }    => *timestamp,
+            InputEvent::CursorEntered { timestamp, .. }  => *timestamp,
+            InputEvent::Scroll { timestamp, .. }         => *timestamp,
+            InputEvent::GamepadConnected { timestamp, .. }    => *timestamp,
+            InputEvent::GamepadDisconnected { timestamp, .. } => *timestamp,
+            InputEvent::GamepadButton { timestamp, .. }       => *timestamp,
+        }
+    }
+
+    /// The same event at another time, e.g. relative to the start of a recording
+    pub fn with_timestamp(mut self, time: f64) -> Self {
+        match &mut self {
+            InputEvent::Key { timestamp, .. }
+                | InputEvent::KeyRepeat { timestamp, .. }
+                | InputEvent::Text { timestamp, .. }
+                | InputEvent::CursorMoved { timestamp, .. }
+                | InputEvent::CursorEntered { timestamp, .. }
+                | InputEvent::Scroll { timestamp, .. }
+                | InputEvent::GamepadConnected { timestamp, .. }
+                | InputEvent::GamepadDisconnected { timestamp, .. }
+                | InputEvent::GamepadButton { timestamp, .. } => *timestamp = time,
+        }
+        self
+    }
+
+    pub fn source(&self) -> InputSource {
+        match self {
+            InputEvent::Key { source, .. } => *source,
+            InputEvent::KeyRepeat { .. } | InputEvent::Text { .. } => InputSource::Keyboard,
+            InputEvent::GamepadConnected { .. }
+                | InputEvent::GamepadDisconnected { .. }
+                | InputEvent::GamepadButton { .. } => InputSource::Gamepad,
+            _ => InputSource::Mouse,
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        match self {
+            InputEvent::Key { modifiers, .. } => *modifiers,
+            InputEvent::KeyRepeat { modifiers, .. } => *modifiers,
+            _ => Modifiers::NONE,
+        }
+    }
+}
@@ -1,10 +1,21 @@
-use glfw::{Glfw, GlfwReceiver, WindowEvent};
+use crate::input::input_event::{InputEvent, InputSource, Modifiers};
+use crate::input::action_map::ActionMap;
+use crate::input::gamepad::{GamepadManager, GlfwGamepadBackend, SimulatedGamepadBackend};
+use crate::input::recording::InputRecording;
+use crate::input::shortcut::Shortcut;
+use crate::input::gesture::{Gesture, GestureRecognizer, GestureSettings};
+
+use crate::video::window_event::WindowEvent;
+
+use glfw::{Glfw, GlfwReceiver};
 use std::collections::HashMap;
+use std::time::Instant;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Key {
     // Mouse
     MouseLeft, MouseRight, MouseMiddle, MouseScrollUp, MouseScrollDown,
+    MouseButton4, MouseButton5, MouseButton6, MouseButton7, MouseButton8,
 
     // Alpha
     A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, 
@@ -29,10 +40,89 @@ pub enum Key {
 
     // Function Keys
     F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, F25,
+
+    // System Keys
+    PrintScreen, Pause, Menu,
+
+    // Non-US layout keys, usually the key left of 1 or right of the left shift
+    World1, World2,
+
+    /// A key GLFW has no name for, holds the platform specific scancode
+    Unknown(i32),
 
     None,
 }
 
+impl Key {
+    /// The key that types `character` on a US keyboard layout
+    pub fn from_char(character: char) -> Option<Key> {
+        let key = match character.to_ascii_lowercase() {
+            'a' => Key::A, 'b' => Key::B, 'c' => Key::C, 'd' => Key::D, 'e' => Key::E, 'f' => Key::F,
+            'g' => Key::G, 'h' => Key::H, 'i' => Key::I, 'j' => Key::J, 'k' => Key::K, 'l' => Key::L,
+            'm' => Key::M, 'n' => Key::N, 'o' => Key::O, 'p' => Key::P, 'q' => Key::Q, 'r' => Key::R,
+            's' => Key::S, 't' => Key::T, 'u' => Key::U, 'v' => Key::V, 'w' => Key::W, 'x' => Key::X,
+            'y' => Key::Y, 'z' => Key::Z,
+            '1' => Key::Number1, '2' => Key::Number2, '3' => Key::Number3, '4' => Key::Number4,
+            '5' => Key::Number5, '6' => Key::Number6, '7' => Key::Number7, '8' => Key::Number8,
+            '9' => Key::Number9, '0' => Key::Number0,
+            '.' => Key::Period, ',' => Key::Comma, '/' => Key::ForwardSlash, '\\' => Key::BackSlash,
+            ' ' => Key::Space, '=' => Key::Equals, '-' => Key::Minus, '`' => Key::Grave,
+            '[' => Key::LeftBracket, ']' => Key::RightBracket, '\'' => Key::Apostrophe, ';' => Key::SemiColon,
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// Every named key, `Key::Unknown` and `Key::None` aren't included
+    pub const ALL: &'static [Key] = &[
+        Key::MouseLeft, Key::MouseRight, Key::MouseMiddle, Key::MouseScrollUp, Key::MouseScrollDown,
+        Key::MouseButton4, Key::MouseButton5, Key::MouseButton6, Key::MouseButton7, Key::MouseButton8,
+        Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+        Key::Period, Key::Comma, Key::ForwardSlash, Key::BackSlash, Key::Space, Key::Equals, Key::Minus, Key::Grave,
+        Key::Enter, Key::Escape, Key::Tab, Key::Backspace, Key::LeftBracket, Key::RightBracket, Key::Delete, Key::Apostrophe, Key::SemiColon,
+        Key::Number1, Key::Number2, Key::Number3, Key::Number4, Key::Number5, Key::Number6, Key::Number7, Key::Number8, Key::Number9, Key::Number0,
+        Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPad0,
+        Key::NumPadDecimal, Key::NumPadEquals, Key::NumPadEnter, Key::NumPadMinus, Key::NumPadAdd, Key::NumPadDivide, Key::NumPadMultiply,
+        Key::LeftShift, Key::RightShift, Key::LeftControl, Key::RightControl, Key::LeftAlt, Key::RightAlt, Key::LeftSuper, Key::RightSuper,
+        Key::CapsLock, Key::NumLock, Key::ScrollLock,
+        Key::ArrowRight, Key::ArrowLeft, Key::ArrowDown, Key::ArrowUp, Key::Home, Key::End, Key::PageUp, Key::PageDown, Key::Insert,
+        Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+        Key::F13, Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19, Key::F20, Key::F21, Key::F22, Key::F23, Key::F24, Key::F25,
+        Key::PrintScreen, Key::Pause, Key::Menu,
+        Key::World1, Key::World2,
+    ];
+}
+
+/// The variant name, e.g. "Space", "MouseLeft" or "Unknown(93)". Parsing accepts the same
+/// names in any case.
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::str::FromStr for Key {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        if name == "None" {
+            return Ok(Key::None);
+        }
+        if let Some(scancode) = name.strip_prefix("Unknown(").and_then(|rest| rest.strip_suffix(')')) {
+            return scancode.trim().parse::<i32>()
+                .map(Key::Unknown)
+                .map_err(|_| format!("invalid scancode in '{}'", name));
+        }
+        Key::ALL.iter()
+            .find(|key| key.to_string().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| format!("unknown key '{}'", name))
+    }
+}
+
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Action {
     Pressed,
@@ -43,177 +133,852 @@ pub enum Action {
 
 pub struct InputManager {
     key_states: HashMap<Key, Action>,
-    glfw_context: Glfw,
-    event_listener: GlfwReceiver<(f64, WindowEvent)>,
+    /// None when headless, there is no window to get events from
+    glfw_context: Option<Glfw>,
+    event_listener: Option<GlfwReceiver<(f64, glfw::WindowEvent)>>,
+    /// Clock of headless engines, GLFW's timer only runs once GLFW is initialized
+    start_time: Instant,
+    is_frame_polled: bool,
+    /// In window coordinates, origin at the top left
+    cursor_position: Option<(f64, f64)>,
+    cursor_delta: (f64, f64),
+    is_cursor_in_window: bool,
+    cursor_entered: bool,
+    cursor_left: bool,
+    scroll_delta: (f64, f64),
+    /// Every event of the current frame in the order GLFW reported them
+    events: Vec<InputEvent>,
+    action_map: ActionMap,
+    /// Text typed this frame
+    text_input: String,
+    /// Keys the OS repeated this frame because they were held down
+    repeated_keys: Vec<Key>,
+    /// Caps lock and num lock as reported with the last key event
+    lock_modifiers: Modifiers,
+    gamepads: GamepadManager,
+    gesture_recognizer: GestureRecognizer,
+    /// Gestures completed this frame, positions in window pixels
+    gestures: Vec<Gesture>,
+    window_events: Vec<WindowEvent>,
+    /// Time of the current frame, taken from the recording while replaying
+    frame_time: f64,
+    recording: Option<InputRecording>,
+    recording_frame: u64,
+    /// Frame time the recording started at, recorded times are relative to it
+    recording_start: f64,
+    playback: Option<InputRecording>,
+    playback_frame: u64,
 }
 
 impl InputManager {
-    pub fn new(glfw_context: Glfw, event_listener: GlfwReceiver<(f64, WindowEvent)>) -> Self {
+    pub fn new(glfw_context: Glfw, event_listener: GlfwReceiver<(f64, glfw::WindowEvent)>) -> Self {
+        let gamepads = GamepadManager::new(Box::new(GlfwGamepadBackend::new(glfw_context.clone())));
+        Self::build(Some(glfw_context), Some(event_listener), gamepads)
+    }
+
+    /// Input for an engine without a window. Only replayed recordings and simulated gamepads
+    /// produce events.
+    pub fn headless() -> Self {
+        Self::build(None, None, GamepadManager::new(Box::new(SimulatedGamepadBackend::new())))
+    }
+
+    fn build(
+        glfw_context: Option<Glfw>,
+        event_listener: Option<GlfwReceiver<(f64, glfw::WindowEvent)>>,
+        gamepads: GamepadManager,
+    ) -> Self {
         InputManager{
             key_states: HashMap::new(),
             glfw_context,
             event_listener,
+            start_time: Instant::now(),
+            is_frame_polled: false,
+            cursor_position: None,
+            cursor_delta: (0.0, 0.0),
+            is_cursor_in_window: false,
+            cursor_entered: false,
+            cursor_left: false,
+            scroll_delta: (0.0, 0.0),
+            events: Vec::new(),
+            action_map: ActionMap::new(),
+            text_input: String::new(),
+            repeated_keys: Vec::new(),
+            lock_modifiers: Modifiers::NONE,
+            gamepads,
+            gesture_recognizer: GestureRecognizer::default(),
+            gestures: Vec::new(),
+            window_events: Vec::new(),
+            frame_time: 0.0,
+            recording: None,
+            recording_frame: 0,
+            recording_start: 0.0,
+            playback: None,
+            playback_frame: 0,
         }
     }
 
-    pub fn read_events(&mut self) -> HashMap<Key, Action> {
-        self.glfw_context.poll_events();
+    /// Last known cursor position in window pixels, origin at the top left of the window
+    pub fn cursor_position(&mut self) -> Option<(f64, f64)> {
+        self.update();
+        self.cursor_position
+    }
 
-        for value in self.key_states.values_mut() {
-            match *value {
-                Action::Pressed => *value = Action::Held,
-                Action::Released => *value = Action::None,
-                _ => {},
+    /// How far the cursor moved in window pixels since the previous frame
+    pub fn cursor_delta(&mut self) -> (f64, f64) {
+        self.update();
+        self.cursor_delta
+    }
+
+    pub fn is_cursor_in_window(&mut self) -> bool {
+        self.update();
+        self.is_cursor_in_window
+    }
+
+    /// True if the cursor entered the window this frame
+    pub fn cursor_entered(&mut self) -> bool {
+        self.update();
+        self.cursor_entered
+    }
+
+    /// True if the cursor left the window this frame
+    pub fn cursor_left(&mut self) -> bool {
+        self.update();
+        self.cursor_left
+    }
+
+    /// Total (horizontal, vertical) scroll offset this frame. Positive y scrolls up and positive
+    /// x scrolls right, a regular mouse wheel moves 1.0 per notch while trackpads and high
+    /// resolution wheels report fractions.
+    pub fn scroll_delta(&mut self) -> (f64, f64) {
+        self.update();
+        self.scroll_delta
+    }
+
+    /// Text typed this frame as UTF-8, after any IME composition. Requires
+    /// `EngineBuilder::poll_keyboard`.
+    pub fn text_input(&mut self) -> String {
+        self.update();
+        self.text_input.clone()
+    }
+
+    /// True if the OS sent a key repeat for `key` this frame. Repeats start after the OS key
+    /// repeat delay and keep firing at its repeat rate while the key is held.
+    pub fn is_key_repeated(&mut self, key: Key) -> bool {
+        self.update();
+        self.repeated_keys.contains(&key)
+    }
+
+    /// Modifier keys currently held down
+    pub fn modifiers(&mut self) -> Modifiers {
+        self.update();
+        let is_down = |keys: [Key; 2]| keys.iter()
+            .any(|key| matches!(self.key_states.get(key), Some(Action::Pressed | Action::Held)));
+
+        Modifiers {
+            shift: is_down([Key::LeftShift, Key::RightShift]),
+            control: is_down([Key::LeftControl, Key::RightControl]),
+            alt: is_down([Key::LeftAlt, Key::RightAlt]),
+            super_key: is_down([Key::LeftSuper, Key::RightSuper]),
+            caps_lock: self.lock_modifiers.caps_lock,
+            num_lock: self.lock_modifiers.num_lock,
+        }
+    }
+
+    /// What the key prints in the current keyboard layout, e.g. "z" for `Key::W` on AZERTY.
+    /// None for keys that don't print anything such as arrows and mouse buttons.
+    pub fn key_name(&self, key: Key) -> Option<String> {
+        // GLFW isn't initialized without a window
+        self.glfw_context.as_ref()?;
+        match key {
+            Key::Unknown(scancode) => glfw::get_key_name(None, Some(scancode)),
+            _ => glfw::get_key_name(Some(to_glfw_key(key)?), None),
+        }
+    }
+
+    /// True if a key was pressed this frame that triggers `shortcut` with the modifiers held
+    pub fn is_shortcut_pressed(&mut self, shortcut: &Shortcut) -> bool {
+        let modifiers = self.modifiers();
+        let pressed: Vec<Key> = self.key_states.iter()
+            .filter(|(_, action)| **action == Action::Pressed)
+            .map(|(key, _)| *key)
+            .collect();
+        pressed.into_iter().any(|key| shortcut.matches(key, modifiers, self.key_name(key).as_deref()))
+    }
+
+    /// Like `is_shortcut_pressed` but also true when the OS repeats the key while it's held,
+    /// useful for undo and redo
+    pub fn is_shortcut_repeated(&mut self, shortcut: &Shortcut) -> bool {
+        if self.is_shortcut_pressed(shortcut) {
+            return true;
+        }
+        let modifiers = self.modifiers();
+        self.repeated_keys.iter().any(|key| shortcut.matches(*key, modifiers, self.key_name(*key).as_deref()))
+    }
+
+    /// Clicks, double clicks, drags and long presses completed this frame, positions are in
+    /// window pixels. Requires `EngineBuilder::poll_mouse_buttons` and `poll_cursor`.
+    pub fn gestures(&mut self) -> Vec<Gesture> {
+        self.update();
+        self.gestures.clone()
+    }
+
+    pub fn get_gesture_settings(&self) -> GestureSettings {
+        self.gesture_recognizer.get_settings()
+    }
+
+    pub fn set_gesture_settings(&mut self, settings: GestureSettings) {
+        self.gesture_recognizer.set_settings(settings);
+    }
+
+    /// Takes the window events of the current frame, in the order they happened
+    pub fn drain_window_events(&mut self) -> Vec<WindowEvent> {
+        self.update();
+        std::mem::take(&mut self.window_events)
+    }
+
+    /// Takes every input event of the current frame in the order they happened. Unlike
+    /// `read_events` this keeps presses and releases that happened within the same frame.
+    pub fn drain_events(&mut self) -> Vec<InputEvent> {
+        self.update();
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn get_action_map(&mut self) -> &mut ActionMap {
+        &mut self.action_map
+    }
+
+    pub fn set_action_map(&mut self, action_map: ActionMap) {
+        self.action_map = action_map;
+    }
+
+    pub fn is_action_pressed(&mut self, action: &str) -> bool {
+        self.update();
+        self.action_map.is_action_pressed(action, &self.key_states)
+    }
+
+    pub fn is_action_held(&mut self, action: &str) -> bool {
+        self.update();
+        self.action_map.is_action_held(action, &self.key_states)
+    }
+
+    pub fn is_action_released(&mut self, action: &str) -> bool {
+        self.update();
+        self.action_map.is_action_released(action, &self.key_states)
+    }
+
+    pub fn axis_value(&mut self, axis: &str) -> f32 {
+        self.update();
+        self.action_map.axis_value(axis, &self.key_states)
+    }
+
+    /// Polls the gamepads for this frame if that hasn't happened yet
+    pub fn get_gamepads(&mut self) -> &mut GamepadManager {
+        self.update();
+        &mut self.gamepads
+    }
+
+    /// Marks the end of a frame, the next query polls GLFW for new events.
+    pub fn end_frame(&mut self) {
+        if self.recording.is_some() || self.playback.is_some() {
+            // frames nobody queried still have to be polled, otherwise their events end up in
+            // a later frame and the frame numbers don't line up on replay
+            self.update();
+        }
+        self.is_frame_polled = false;
+
+        if self.recording.is_some() {
+            self.recording_frame += 1;
+        }
+        if let Some(recording) = self.playback.as_ref() {
+            self.playback_frame += 1;
+            if self.playback_frame >= recording.get_frame_count() {
+                self.stop_playback();
             }
         }
-        self.key_states.retain(|_, value| *value != Action::None);
+    }
 
-        for (_, event) in glfw::flush_messages(&self.event_listener) {
-            let mut action: Action = Action::None;
-            let mut key: Key = Key::None;
+    /// Starts recording keyboard and mouse input, the current frame is frame 0. Keys that are
+    /// already down and the cursor position are stored first so a replay starts from the same
+    /// state.
+    pub fn start_recording(&mut self) {
+        // the snapshot has to hold this frame's key states, last frame's Pressed keys would be
+        // replayed as pressed a second time
+        self.update();
 
-            match event {
-                WindowEvent::Key(_, _, glfw::Action::Press, _) => action = Action::Pressed,
-                WindowEvent::Key(_, _, glfw::Action::Release, _) => action = Action::Released,
+        self.recording_start = self.frame_time;
+        let timestamp = 0.0;
+        let mut keys: Vec<(Key, Action)> = self.key_states.iter().map(|(key, action)| (*key, *action)).collect();
+        keys.sort_by_key(|(key, _)| key.to_string());
 
-                WindowEvent::MouseButton(_, glfw::Action::Press, _) => action = Action::Pressed,
-                WindowEvent::MouseButton(_, glfw::Action::Release, _) => action = Action::Released,
-                _ => {},
+        let mut initial_state: Vec<InputEvent> = keys.into_iter()
+            .map(|(key, action)| InputEvent::Key {
+                key,
+                action,
+                modifiers: Modifiers::NONE,
+                source: key_source(key),
+                timestamp,
+            })
+            .collect();
+        if let Some((x, y)) = self.cursor_position {
+            initial_state.push(InputEvent::CursorMoved { x, y, timestamp });
+        }
+        if self.is_cursor_in_window {
+            initial_state.push(InputEvent::CursorEntered { entered: true, timestamp });
+        }
+        // text, repeats and scrolling of this frame aren't part of the state above
+        initial_state.extend(self.events.iter()
+            .filter(|event| !matches!(event, InputEvent::Key { .. } | InputEvent::CursorMoved { .. } | InputEvent::CursorEntered { .. }))
+            .map(|event| event.clone().with_timestamp(event.timestamp() - self.recording_start)));
+
+        let mut recording = InputRecording::new();
+        recording.push_frame(0, 0.0, &initial_state);
+        self.recording = Some(recording);
+        self.recording_frame = 0;
+    }
+
+    /// Stops recording and returns everything recorded so far
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replaces keyboard and mouse input with `recording`, starting with the current frame.
+    /// Live keyboard, mouse and gamepad events are dropped until the recording ends or
+    /// `stop_playback` is called. Event timestamps and gesture timing come from the recording.
+    /// Run the game at a fixed timestep for an exact replay.
+    pub fn start_playback(&mut self, recording: InputRecording) {
+        self.key_states.clear();
+        self.cursor_position = None;
+        self.cursor_delta = (0.0, 0.0);
+        self.is_cursor_in_window = false;
+        self.cursor_entered = false;
+        self.cursor_left = false;
+        self.scroll_delta = (0.0, 0.0);
+        self.text_input.clear();
+        self.repeated_keys.clear();
+        self.events.clear();
+        self.playback_frame = 0;
+        self.reset_gestures();
+
+        // the current frame was already polled, so apply frame 0 right away
+        if self.is_frame_polled {
+            self.frame_time = recording.get_frame_time(0);
+            for event in recording.get_frame(0).to_vec() {
+                self.apply_event(&event);
+                self.events.push(event);
             }
+            self.update_gestures();
+        }
+        self.playback = Some(recording);
+    }
+
+    pub fn stop_playback(&mut self) {
+        if self.playback.take().is_some() {
+            // presses from the recording would otherwise be timed against the live clock
+            self.reset_gestures();
+        }
+    }
 
-            match event {
-                // mouse
-                WindowEvent::MouseButton(glfw::MouseButton::Button1, _, _) => key = Key::MouseLeft,
-                WindowEvent::MouseButton(glfw::MouseButton::Button2, _, _) => key = Key::MouseRight,
-                WindowEvent::MouseButton(glfw::MouseButton::Button3, _, _) => key = Key::MouseMiddle,
-
-                // scrolls
-                WindowEvent::Scroll(_, y_offset) => if y_offset > 0.0001 {key = Key::MouseScrollUp}
-                                                    else if y_offset < -0.0001 {key = Key::MouseScrollDown},
-
-                // alpha
-                WindowEvent::Key(glfw::Key::A, _, _, _) => key = Key::A,
-                WindowEvent::Key(glfw::Key::B, _, _, _) => key = Key::B,
-                WindowEvent::Key(glfw::Key::C, _, _, _) => key = Key::C,
-                WindowEvent::Key(glfw::Key::D, _, _, _) => key = Key::D,
-                WindowEvent::Key(glfw::Key::E, _, _, _) => key = Key::E,
-                WindowEvent::Key(glfw::Key::F, _, _, _) => key = Key::F,
-                WindowEvent::Key(glfw::Key::G, _, _, _) => key = Key::G,
-                WindowEvent::Key(glfw::Key::H, _, _, _) => key = Key::H,
-                WindowEvent::Key(glfw::Key::I, _, _, _) => key = Key::I,
-                WindowEvent::Key(glfw::Key::J, _, _, _) => key = Key::J,
-                WindowEvent::Key(glfw::Key::K, _, _, _) => key = Key::K,
-                WindowEvent::Key(glfw::Key::L, _, _, _) => key = Key::L,
-                WindowEvent::Key(glfw::Key::M, _, _, _) => key = Key::M,
-                WindowEvent::Key(glfw::Key::N, _, _, _) => key = Key::N,
-                WindowEvent::Key(glfw::Key::O, _, _, _) => key = Key::O,
-                WindowEvent::Key(glfw::Key::P, _, _, _) => key = Key::P,
-                WindowEvent::Key(glfw::Key::Q, _, _, _) => key = Key::Q,
-                WindowEvent::Key(glfw::Key::R, _, _, _) => key = Key::R,
-                WindowEvent::Key(glfw::Key::S, _, _, _) => key = Key::S,
-                WindowEvent::Key(glfw::Key::T, _, _, _) => key = Key::T,
-                WindowEvent::Key(glfw::Key::U, _, _, _) => key = Key::U,
-                WindowEvent::Key(glfw::Key::V, _, _, _) => key = Key::V,
-                WindowEvent::Key(glfw::Key::W, _, _, _) => key = Key::W,
-                WindowEvent::Key(glfw::Key::X, _, _, _) => key = Key::X,
-                WindowEvent::Key(glfw::Key::Y, _, _, _) => key = Key::Y,
-                WindowEvent::Key(glfw::Key::Z, _, _, _) => key = Key::Z,
-
-                // function
-                WindowEvent::Key(glfw::Key::F1, _, _, _) => key = Key::F1,
-                WindowEvent::Key(glfw::Key::F2, _, _, _) => key = Key::F2,
-                WindowEvent::Key(glfw::Key::F3, _, _, _) => key = Key::F3,
-                WindowEvent::Key(glfw::Key::F4, _, _, _) => key = Key::F4,
-                WindowEvent::Key(glfw::Key::F5, _, _, _) => key = Key::F5,
-                WindowEvent::Key(glfw::Key::F6, _, _, _) => key = Key::F6,
-                WindowEvent::Key(glfw::Key::F7, _, _, _) => key = Key::F7,
-                WindowEvent::Key(glfw::Key::F8, _, _, _) => key = Key::F8,
-                WindowEvent::Key(glfw::Key::F9, _, _, _) => key = Key::F9,
-                WindowEvent::Key(glfw::Key::F10, _, _, _) => key = Key::F10,
-                WindowEvent::Key(glfw::Key::F11, _, _, _) => key = Key::F11,
-                WindowEvent::Key(glfw::Key::F12, _, _, _) => key = Key::F12,
-
-                // mod
-                WindowEvent::Key(glfw::Key::LeftControl, _, _, _) => key = Key::LeftControl,
-                WindowEvent::Key(glfw::Key::RightControl, _, _, _) => key = Key::RightControl,
-                WindowEvent::Key(glfw::Key::LeftAlt, _, _, _) => key = Key::LeftAlt,
-                WindowEvent::Key(glfw::Key::RightAlt, _, _, _) => key = Key::RightAlt,
-                WindowEvent::Key(glfw::Key::LeftShift, _, _, _) => key = Key::LeftShift,
-                WindowEvent::Key(glfw::Key::RightShift, _, _, _) => key = Key::RightShift,
-                WindowEvent::Key(glfw::Key::LeftSuper, _, _, _) => key = Key::LeftSuper,
-                WindowEvent::Key(glfw::Key::RightSuper, _, _, _) => key = Key::RightSuper,
-                WindowEvent::Key(glfw::Key::CapsLock, _, _, _) => key = Key::CapsLock,
-                WindowEvent::Key(glfw::Key::NumLock, _, _, _) => key = Key::NumLock,
-                WindowEvent::Key(glfw::Key::ScrollLock, _, _, _) => key = Key::ScrollLock,
-
-                // nums
-                WindowEvent::Key(glfw::Key::Num0, _, _, _) => key = Key::Number0,
-                WindowEvent::Key(glfw::Key::Num1, _, _, _) => key = Key::Number1,
-                WindowEvent::Key(glfw::Key::Num2, _, _, _) => key = Key::Number2,
-                WindowEvent::Key(glfw::Key::Num3, _, _, _) => key = Key::Number3,
-                WindowEvent::Key(glfw::Key::Num4, _, _, _) => key = Key::Number4,
-                WindowEvent::Key(glfw::Key::Num5, _, _, _) => key = Key::Number5,
-                WindowEvent::Key(glfw::Key::Num6, _, _, _) => key = Key::Number6,
-                WindowEvent::Key(glfw::Key::Num7, _, _, _) => key = Key::Number7,
-                WindowEvent::Key(glfw::Key::Num8, _, _, _) => key = Key::Number8,
-                WindowEvent::Key(glfw::Key::Num9, _, _, _) => key = Key::Number9,
-
-                // numpad
-                WindowEvent::Key(glfw::Key::Kp0, _, _, _) => key = Key::NumPad0,
-                WindowEvent::Key(glfw::Key::Kp1, _, _, _) => key = Key::NumPad1,
-                WindowEvent::Key(glfw::Key::Kp2, _, _, _) => key = Key::NumPad2,
-                WindowEvent::Key(glfw::Key::Kp3, _, _, _) => key = Key::NumPad3,
-                WindowEvent::Key(glfw::Key::Kp4, _, _, _) => key = Key::NumPad4,
-                WindowEvent::Key(glfw::Key::Kp5, _, _, _) => key = Key::NumPad5,
-                WindowEvent::Key(glfw::Key::Kp6, _, _, _) => key = Key::NumPad6,
-                WindowEvent::Key(glfw::Key::Kp7, _, _, _) => key = Key::NumPad7,
-                WindowEvent::Key(glfw::Key::Kp8, _, _, _) => key = Key::NumPad8,
-                WindowEvent::Key(glfw::Key::Kp9, _, _, _) => key = Key::NumPad9,
-                WindowEvent::Key(glfw::Key::KpMultiply, _, _, _) => key = Key::NumPadMultiply,
-                WindowEvent::Key(glfw::Key::KpDivide, _, _, _) => key = Key::NumPadDivide,
-                WindowEvent::Key(glfw::Key::KpAdd, _, _, _) => key = Key::NumPadAdd,
-                WindowEvent::Key(glfw::Key::KpSubtract, _, _, _) => key = Key::NumPadMinus,
-                WindowEvent::Key(glfw::Key::KpEqual, _, _, _) => key = Key::NumPadEquals,
-                WindowEvent::Key(glfw::Key::KpEnter, _, _, _) => key = Key::NumPadEnter,
-                WindowEvent::Key(glfw::Key::KpDecimal, _, _, _) => key = Key::NumPadDecimal,
-
-                // movement
-                WindowEvent::Key(glfw::Key::Up, _, _, _) => key = Key::ArrowUp,
-                WindowEvent::Key(glfw::Key::Down, _, _, _) => key = Key::ArrowDown,
-                WindowEvent::Key(glfw::Key::Left, _, _, _) => key = Key::ArrowLeft,
-                WindowEvent::Key(glfw::Key::Right, _, _, _) => key = Key::ArrowRight,
-                WindowEvent::Key(glfw::Key::PageUp, _, _, _) => key = Key::PageUp,
-                WindowEvent::Key(glfw::Key::PageDown, _, _, _) => key = Key::PageDown,
-                WindowEvent::Key(glfw::Key::Home, _, _, _) => key = Key::Home,
-                WindowEvent::Key(glfw::Key::End, _, _, _) => key = Key::End,
-                WindowEvent::Key(glfw::Key::Insert, _, _, _) => key = Key::Insert,
-
-                // non-aplha chars
-                WindowEvent::Key(glfw::Key::Escape, _, _, _) => key = Key::Escape,
-                WindowEvent::Key(glfw::Key::Tab, _, _, _) => key = Key::Tab,
-                WindowEvent::Key(glfw::Key::Backspace, _, _, _) => key = Key::Backspace,
-                WindowEvent::Key(glfw::Key::Enter, _, _, _) => key = Key::Enter,
-                WindowEvent::Key(glfw::Key::Space, _, _, _) => key = Key::Space,
-                WindowEvent::Key(glfw::Key::Backslash, _, _, _) => key = Key::BackSlash,
-                WindowEvent::Key(glfw::Key::Slash, _, _, _) => key = Key::ForwardSlash,
-                WindowEvent::Key(glfw::Key::Comma, _, _, _) => key = Key::Comma,
-                WindowEvent::Key(glfw::Key::GraveAccent, _, _, _) => key = Key::Grave,
-                WindowEvent::Key(glfw::Key::Minus, _, _, _) => key = Key::Minus,
-                WindowEvent::Key(glfw::Key::Equal, _, _, _) => key = Key::Equals,
-                WindowEvent::Key(glfw::Key::Period, _, _, _) => key = Key::Period,
-                WindowEvent::Key(glfw::Key::LeftBracket, _, _, _) => key = Key::LeftBracket,
-                WindowEvent::Key(glfw::Key::RightBracket, _, _, _) => key = Key::RightBracket,
-                WindowEvent::Key(glfw::Key::Delete, _, _, _) => key = Key::Delete,
-                WindowEvent::Key(glfw::Key::Apostrophe, _, _, _) => key = Key::Apostrophe,
-                WindowEvent::Key(glfw::Key::Semicolon, _, _, _) => key = Key::SemiColon,
+    /// False once the whole recording has been played
+    pub fn is_playing_back(&self) -> bool {
+        self.playback.is_some()
+    }
 
-                _ => {},
+    pub fn read_events(&mut self) -> HashMap<Key, Action> {
+        self.update();
+        self.key_states.clone()
+    }
+
+    /// Polls GLFW and processes the events once per frame, every query calls this first so
+    /// they all see the same frame.
+    fn update(&mut self) {
+        if self.is_frame_polled {
+            return;
+        }
+        self.is_frame_polled = true;
+        if let Some(glfw) = self.glfw_context.as_mut() {
+            glfw.poll_events();
+        }
+
+        self.begin_frame();
+        let now = match &self.glfw_context {
+            Some(glfw) => glfw.get_time(),
+            None => self.start_time.elapsed().as_secs_f64(),
+        };
+        self.frame_time = now;
+
+        let mut events: Vec<InputEvent> = Vec::new();
+        if let Some(event_listener) = &self.event_listener {
+            for (timestamp, event) in glfw::flush_messages(event_listener) {
+                if let Some(window_event) = WindowEvent::from_glfw(&event) {
+                    self.window_events.push(window_event);
+                }
+                else if let Some(input_event) = translate_event(timestamp, event) {
+                    events.push(input_event);
+                }
             }
+        }
+        // live keyboard and mouse input is dropped while replaying, window events still go through
+        if let Some(recording) = self.playback.as_ref() {
+            events = recording.get_frame(self.playback_frame).to_vec();
+            self.frame_time = recording.get_frame_time(self.playback_frame);
+        }
+        for event in events {
+            self.apply_event(&event);
+            self.events.push(event);
+        }
+
+        if let Some(recording) = self.recording.as_mut() {
+            let events: Vec<InputEvent> = self.events.iter()
+                .map(|event| event.clone().with_timestamp(event.timestamp() - self.recording_start))
+                .collect();
+            recording.push_frame(self.recording_frame, self.frame_time - self.recording_start, &events);
+        }
+
+        self.update_gestures();
+
+        // gamepads are polled rather than sent as window events, so their events come last.
+        // They aren't recorded, so live gamepad input is dropped while replaying.
+        let gamepad_events = self.gamepads.update(now);
+        if self.playback.is_none() {
+            self.events.extend(gamepad_events);
+        }
+    }
+
+    /// Feeds this frame's events to the gesture recognizer and checks for long presses at the
+    /// frame time
+    fn update_gestures(&mut self) {
+        for event in self.events.iter() {
+            self.gestures.extend(self.gesture_recognizer.process(event));
+        }
+        self.gestures.extend(self.gesture_recognizer.update(self.frame_time));
+    }
+
+    /// Forgets held buttons and the last click, keeping the settings
+    fn reset_gestures(&mut self) {
+        self.gesture_recognizer = GestureRecognizer::new(self.gesture_recognizer.get_settings());
+        self.gestures.clear();
+    }
+
+    /// Advances key states from the previous frame and clears the per-frame values
+    fn begin_frame(&mut self) {
+        self.events.clear();
+        self.cursor_delta = (0.0, 0.0);
+        self.cursor_entered = false;
+        self.cursor_left = false;
+        self.scroll_delta = (0.0, 0.0);
+        self.text_input.clear();
+        self.repeated_keys.clear();
+        self.gestures.clear();
+        self.window_events.clear();
 
-            if key != Key::None && action != Action::None {
-                self.key_states.insert(key, action);
+        for (key, value) in self.key_states.iter_mut() {
+            match (*key, *value) {
+                // scrolling has no release event, so the scroll keys are only held for one frame
+                (Key::MouseScrollUp | Key::MouseScrollDown, Action::Pressed) => *value = Action::Released,
+                (_, Action::Pressed) => *value = Action::Held,
+                (_, Action::Released) => *value = Action::None,
+                _ => {},
             }
         }
-        self.key_states.clone()
+        self.key_states.retain(|_, value| *value != Action::None);
+    }
+
+    /// Updates the polled state from a single event. All state is derived from the same
+    /// stream that `drain_events` returns.
+    fn apply_event(&mut self, event: &InputEvent) {
+        match *event {
+            InputEvent::Key { key, action, modifiers, source, .. } => {
+                if key != Key::None && action != Action::None {
+                    self.key_states.insert(key, action);
+                }
+                if source == InputSource::Keyboard {
+                    self.lock_modifiers = modifiers;
+                }
+            },
+            InputEvent::CursorMoved { x, y, .. } => {
+                if let Some((last_x, last_y)) = self.cursor_position {
+                    self.cursor_delta.0 += x - last_x;
+                    self.cursor_delta.1 += y - last_y;
+                }
+                self.cursor_position = Some((x, y));
+            },
+            InputEvent::CursorEntered { entered: true, .. } => {
+                self.is_cursor_in_window = true;
+                self.cursor_entered = true;
+            },
+            InputEvent::CursorEntered { entered: false, .. } => {
+                self.is_cursor_in_window = false;
+                self.cursor_left = true;
+            },
+            InputEvent::Scroll { x, y, .. } => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+
+                if y > 0.0001 {
+                    self.key_states.insert(Key::MouseScrollUp, Action::Pressed);
+                }
+                else if y < -0.0001 {
+                    self.key_states.insert(Key::MouseScrollDown, Action::Pressed);
+                }
+            },
+            InputEvent::KeyRepeat { key, modifiers, .. } => {
+                self.lock_modifiers = modifiers;
+                if key != Key::None {
+                    self.repeated_keys.push(key);
+                }
+            },
+            InputEvent::Text { ref text, .. } => self.text_input.push_str(text),
+            // gamepad state is tracked by the GamepadManager
+            InputEvent::GamepadConnected { .. }
+                | InputEvent::GamepadDisconnected { .. }
+                | InputEvent::GamepadButton { .. } => {},
+        }
+    }
+}
+
+fn translate_event(timestamp: f64, event: glfw::WindowEvent) -> Option<InputEvent> {
+    let action = |action: glfw::Action| match action {
+        glfw::Action::Press => Action::Pressed,
+        glfw::Action::Release => Action::Released,
+        glfw::Action::Repeat => Action::None,
+    };
+
+    match event {
+        glfw::WindowEvent::Key(key, scancode, glfw::Action::Repeat, modifiers) => Some(InputEvent::KeyRepeat {
+            key: map_glfw_key(key, scancode),
+            modifiers: modifiers.into(),
+            timestamp,
+        }),
+        glfw::WindowEvent::Key(key, scancode, glfw_action, modifiers) => Some(InputEvent::Key {
+            key: map_glfw_key(key, scancode),
+            action: action(glfw_action),
+            modifiers: modifiers.into(),
+            source: InputSource::Keyboard,
+            timestamp,
+        }),
+        glfw::WindowEvent::MouseButton(button, glfw_action, modifiers) => Some(InputEvent::Key {
+            key: map_mouse_button(button),
+            action: action(glfw_action),
+            modifiers: modifiers.into(),
+            source: InputSource::Mouse,
+            timestamp,
+        }),
+        glfw::WindowEvent::CursorPos(x, y) => Some(InputEvent::CursorMoved { x, y, timestamp }),
+        glfw::WindowEvent::CursorEnter(entered) => Some(InputEvent::CursorEntered { entered, timestamp }),
+        glfw::WindowEvent::Scroll(x, y) => Some(InputEvent::Scroll { x, y, timestamp }),
+        glfw::WindowEvent::Char(character) => Some(InputEvent::Text { text: character.to_string(), timestamp }),
+        _ => None,
+    }
+}
+
+fn key_source(key: Key) -> InputSource {
+    match key {
+        Key::MouseLeft | Key::MouseRight | Key::MouseMiddle | Key::MouseScrollUp | Key::MouseScrollDown
+            | Key::MouseButton4 | Key::MouseButton5 | Key::MouseButton6 | Key::MouseButton7
+            | Key::MouseButton8 => InputSource::Mouse,
+        _ => InputSource::Keyboard,
+    }
+}
+
+fn map_mouse_button(button: glfw::MouseButton) -> Key {
+    match button {
+        glfw::MouseButton::Button1 => Key::MouseLeft,
+        glfw::MouseButton::Button2 => Key::MouseRight,
+        glfw::MouseButton::Button3 => Key::MouseMiddle,
+        glfw::MouseButton::Button4 => Key::MouseButton4,
+        glfw::MouseButton::Button5 => Key::MouseButton5,
+        glfw::MouseButton::Button6 => Key::MouseButton6,
+        glfw::MouseButton::Button7 => Key::MouseButton7,
+        glfw::MouseButton::Button8 => Key::MouseButton8,
+    }
+}
+
+fn map_glfw_key(key: glfw::Key, scancode: glfw::Scancode) -> Key {
+    match key {
+        // alpha
+        glfw::Key::A => Key::A,
+        glfw::Key::B => Key::B,
+        glfw::Key::C => Key::C,
+        glfw::Key::D => Key::D,
+        glfw::Key::E => Key::E,
+        glfw::Key::F => Key::F,
+        glfw::Key::G => Key::G,
+        glfw::Key::H => Key::H,
+        glfw::Key::I => Key::I,
+        glfw::Key::J => Key::J,
+        glfw::Key::K => Key::K,
+        glfw::Key::L => Key::L,
+        glfw::Key::M => Key::M,
+        glfw::Key::N => Key::N,
+        glfw::Key::O => Key::O,
+        glfw::Key::P => Key::P,
+        glfw::Key::Q => Key::Q,
+        glfw::Key::R => Key::R,
+        glfw::Key::S => Key::S,
+        glfw::Key::T => Key::T,
+        glfw::Key::U => Key::U,
+        glfw::Key::V => Key::V,
+        glfw::Key::W => Key::W,
+        glfw::Key::X => Key::X,
+        glfw::Key::Y => Key::Y,
+        glfw::Key::Z => Key::Z,
+
+        // function
+        glfw::Key::F1 => Key::F1,
+        glfw::Key::F2 => Key::F2,
+        glfw::Key::F3 => Key::F3,
+        glfw::Key::F4 => Key::F4,
+        glfw::Key::F5 => Key::F5,
+        glfw::Key::F6 => Key::F6,
+        glfw::Key::F7 => Key::F7,
+        glfw::Key::F8 => Key::F8,
+        glfw::Key::F9 => Key::F9,
+        glfw::Key::F10 => Key::F10,
+        glfw::Key::F11 => Key::F11,
+        glfw::Key::F12 => Key::F12,
+        glfw::Key::F13 => Key::F13,
+        glfw::Key::F14 => Key::F14,
+        glfw::Key::F15 => Key::F15,
+        glfw::Key::F16 => Key::F16,
+        glfw::Key::F17 => Key::F17,
+        glfw::Key::F18 => Key::F18,
+        glfw::Key::F19 => Key::F19,
+        glfw::Key::F20 => Key::F20,
+        glfw::Key::F21 => Key::F21,
+        glfw::Key::F22 => Key::F22,
+        glfw::Key::F23 => Key::F23,
+        glfw::Key::F24 => Key::F24,
+        glfw::Key::F25 => Key::F25,
+
+        // mod
+        glfw::Key::LeftControl => Key::LeftControl,
+        glfw::Key::RightControl => Key::RightControl,
+        glfw::Key::LeftAlt => Key::LeftAlt,
+        glfw::Key::RightAlt => Key::RightAlt,
+        glfw::Key::LeftShift => Key::LeftShift,
+        glfw::Key::RightShift => Key::RightShift,
+        glfw::Key::LeftSuper => Key::LeftSuper,
+        glfw::Key::RightSuper => Key::RightSuper,
+        glfw::Key::CapsLock => Key::CapsLock,
+        glfw::Key::NumLock => Key::NumLock,
+        glfw::Key::ScrollLock => Key::ScrollLock,
+
+        // nums
+        glfw::Key::Num0 => Key::Number0,
+        glfw::Key::Num1 => Key::Number1,
+        glfw::Key::Num2 => Key::Number2,
+        glfw::Key::Num3 => Key::Number3,
+        glfw::Key::Num4 => Key::Number4,
+        glfw::Key::Num5 => Key::Number5,
+        glfw::Key::Num6 => Key::Number6,
+        glfw::Key::Num7 => Key::Number7,
+        glfw::Key::Num8 => Key::Number8,
+        glfw::Key::Num9 => Key::Number9,
+
+        // numpad
+        glfw::Key::Kp0 => Key::NumPad0,
+        glfw::Key::Kp1 => Key::NumPad1,
+        glfw::Key::Kp2 => Key::NumPad2,
+        glfw::Key::Kp3 => Key::NumPad3,
+        glfw::Key::Kp4 => Key::NumPad4,
+        glfw::Key::Kp5 => Key::NumPad5,
+        glfw::Key::Kp6 => Key::NumPad6,
+        glfw::Key::Kp7 => Key::NumPad7,
+        glfw::Key::Kp8 => Key::NumPad8,
+        glfw::Key::Kp9 => Key::NumPad9,
+        glfw::Key::KpMultiply => Key::NumPadMultiply,
+        glfw::Key::KpDivide => Key::NumPadDivide,
+        glfw::Key::KpAdd => Key::NumPadAdd,
+        glfw::Key::KpSubtract => Key::NumPadMinus,
+        glfw::Key::KpEqual => Key::NumPadEquals,
+        glfw::Key::KpEnter => Key::NumPadEnter,
+        glfw::Key::KpDecimal => Key::NumPadDecimal,
+
+        // movement
+        glfw::Key::Up => Key::ArrowUp,
+        glfw::Key::Down => Key::ArrowDown,
+        glfw::Key::Left => Key::ArrowLeft,
+        glfw::Key::Right => Key::ArrowRight,
+        glfw::Key::PageUp => Key::PageUp,
+        glfw::Key::PageDown => Key::PageDown,
+        glfw::Key::Home => Key::Home,
+        glfw::Key::End => Key::End,
+        glfw::Key::Insert => Key::Insert,
+
+        // non-aplha chars
+        glfw::Key::Escape => Key::Escape,
+        glfw::Key::Tab => Key::Tab,
+        glfw::Key::Backspace => Key::Backspace,
+        glfw::Key::Enter => Key::Enter,
+        glfw::Key::Space => Key::Space,
+        glfw::Key::Backslash => Key::BackSlash,
+        glfw::Key::Slash => Key::ForwardSlash,
+        glfw::Key::Comma => Key::Comma,
+        glfw::Key::GraveAccent => Key::Grave,
+        glfw::Key::Minus => Key::Minus,
+        glfw::Key::Equal => Key::Equals,
+        glfw::Key::Period => Key::Period,
+        glfw::Key::LeftBracket => Key::LeftBracket,
+        glfw::Key::RightBracket => Key::RightBracket,
+        glfw::Key::Delete => Key::Delete,
+        glfw::Key::Apostrophe => Key::Apostrophe,
+        glfw::Key::Semicolon => Key::SemiColon,
+
+        // system
+        glfw::Key::PrintScreen => Key::PrintScreen,
+        glfw::Key::Pause => Key::Pause,
+        glfw::Key::Menu => Key::Menu,
+        glfw::Key::World1 => Key::World1,
+        glfw::Key::World2 => Key::World2,
+
+        glfw::Key::Unknown => Key::Unknown(scancode),
     }
 }
+
+/// The GLFW key for `key`, None for mouse buttons and keys GLFW has no name for
+fn to_glfw_key(key: Key) -> Option<glfw::Key> {
+    let glfw_key = match key {
+        // alpha
+        Key::A => glfw::Key::A,
+        Key::B => glfw::Key::B,
+        Key::C => glfw::Key::C,
+        Key::D => glfw::Key::D,
+        Key::E => glfw::Key::E,
+        Key::F => glfw::Key::F,
+        Key::G => glfw::Key::G,
+        Key::H => glfw::Key::H,
+        Key::I => glfw::Key::I,
+        Key::J => glfw::Key::J,
+        Key::K => glfw::Key::K,
+        Key::L => glfw::Key::L,
+        Key::M => glfw::Key::M,
+        Key::N => glfw::Key::N,
+        Key::O => glfw::Key::O,
+        Key::P => glfw::Key::P,
+        Key::Q => glfw::Key::Q,
+        Key::R => glfw::Key::R,
+        Key::S => glfw::Key::S,
+        Key::T => glfw::Key::T,
+        Key::U => glfw::Key::U,
+        Key::V => glfw::Key::V,
+        Key::W => glfw::Key::W,
+        Key::X => glfw::Key::X,
+        Key::Y => glfw::Key::Y,
+        Key::Z => glfw::Key::Z,
+
+        // function
+        Key::F1 => glfw::Key::F1,
+        Key::F2 => glfw::Key::F2,
+        Key::F3 => glfw::Key::F3,
+        Key::F4 => glfw::Key::F4,
+        Key::F5 => glfw::Key::F5,
+        Key::F6 => glfw::Key::F6,
+        Key::F7 => glfw::Key::F7,
+        Key::F8 => glfw::Key::F8,
+        Key::F9 => glfw::Key::F9,
+        Key::F10 => glfw::Key::F10,
+        Key::F11 => glfw::Key::F11,
+        Key::F12 => glfw::Key::F12,
+        Key::F13 => glfw::Key::F13,
+        Key::F14 => glfw::Key::F14,
+        Key::F15 => glfw::Key::F15,
+        Key::F16 => glfw::Key::F16,
+        Key::F17 => glfw::Key::F17,
+        Key::F18 => glfw::Key::F18,
+        Key::F19 => glfw::Key::F19,
+        Key::F20 => glfw::Key::F20,
+        Key::F21 => glfw::Key::F21,
+        Key::F22 => glfw::Key::F22,
+        Key::F23 => glfw::Key::F23,
+        Key::F24 => glfw::Key::F24,
+        Key::F25 => glfw::Key::F25,
+
+        // mod
+        Key::LeftControl => glfw::Key::LeftControl,
+        Key::RightControl => glfw::Key::RightControl,
+        Key::LeftAlt => glfw::Key::LeftAlt,
+        Key::RightAlt => glfw::Key::RightAlt,
+        Key::LeftShift => glfw::Key::LeftShift,
+        Key::RightShift => glfw::Key::RightShift,
+        Key::LeftSuper => glfw::Key::LeftSuper,
+        Key::RightSuper => glfw::Key::RightSuper,
+        Key::CapsLock => glfw::Key::CapsLock,
+        Key::NumLock => glfw::Key::NumLock,
+        Key::ScrollLock => glfw::Key::ScrollLock,
+
+        // nums
+        Key::Number0 => glfw::Key::Num0,
+        Key::Number1 => glfw::Key::Num1,
+        Key::Number2 => glfw::Key::Num2,
+        Key::Number3 => glfw::Key::Num3,
+        Key::Number4 => glfw::Key::Num4,
+        Key::Number5 => glfw::Key::Num5,
+        Key::Number6 => glfw::Key::Num6,
+        Key::Number7 => glfw::Key::Num7,
+        Key::Number8 => glfw::Key::Num8,
+        Key::Number9 => glfw::Key::Num9,
+
+        // numpad
+        Key::NumPad0 => glfw::Key::Kp0,
+        Key::NumPad1 => glfw::Key::Kp1,
+        Key::NumPad2 => glfw::Key::Kp2,
+        Key::NumPad3 => glfw::Key::Kp3,
+        Key::NumPad4 => glfw::Key::Kp4,
+        Key::NumPad5 => glfw::Key::Kp5,
+        Key::NumPad6 => glfw::Key::Kp6,
+        Key::NumPad7 => glfw::Key::Kp7,
+        Key::NumPad8 => glfw::Key::Kp8,
+        Key::NumPad9 => glfw::Key::Kp9,
+        Key::NumPadMultiply => glfw::Key::KpMultiply,
+        Key::NumPadDivide => glfw::Key::KpDivide,
+        Key::NumPadAdd => glfw::Key::KpAdd,
+        Key::NumPadMinus => glfw::Key::KpSubtract,
+        Key::NumPadEquals => glfw::Key::KpEqual,
+        Key::NumPadEnter => glfw::Key::KpEnter,
+        Key::NumPadDecimal => glfw::Key::KpDecimal,
+
+        // movement
+        Key::ArrowUp => glfw::Key::Up,
+        Key::ArrowDown => glfw::Key::Down,
+        Key::ArrowLeft => glfw::Key::Left,
+        Key::ArrowRight => glfw::Key::Right,
+        Key::PageUp => glfw::Key::PageUp,
+        Key::PageDown => glfw::Key::PageDown,
+        Key::Home => glfw::Key::Home,
+        Key::End => glfw::Key::End,
+        Key::Insert => glfw::Key::Insert,
+
+        // non-aplha chars
+        Key::Escape => glfw::Key::Escape,
+        Key::Tab => glfw::Key::Tab,
+        Key::Backspace => glfw::Key::Backspace,
+        Key::Enter => glfw::Key::Enter,
+        Key::Space => glfw::Key::Space,
+        Key::BackSlash => glfw::Key::Backslash,
+        Key::ForwardSlash => glfw::Key::Slash,
+        Key::Comma => glfw::Key::Comma,
+        Key::Grave => glfw::Key::GraveAccent,
+        Key::Minus => glfw::Key::Minus,
+        Key::Equals => glfw::Key::Equal,
+        Key::Period => glfw::Key::Period,
+        Key::LeftBracket => glfw::Key::LeftBracket,
+        Key::RightBracket => glfw::Key::RightBracket,
+        Key::Delete => glfw::Key::Delete,
+        Key::Apostrophe => glfw::Key::Apostrophe,
+        Key::SemiColon => glfw::Key::Semicolon,
+
+        // system
+        Key::PrintScreen => glfw::Key::PrintScreen,
+        Key::Pause => glfw::Key::Pause,
+        Key::Menu => glfw::Key::Menu,
+        Key::World1 => glfw::Key::World1,
+        Key::World2 => glfw::Key::World2,
+
+        _ => return None,
+    };
+    Some(glfw_key)
+}
@@ -1 +1,8 @@
+pub mod action_map;
+pub mod gamepad;
+pub mod gesture;
+pub mod input_event;
 pub mod input_manager;
+pub mod recording;
+pub mod shortcut;
+pub mod text_buffer;
@@ -0,0 +1,405 @@
+use crate::input::input_event::{InputEvent, InputSource, Modifiers};
+use crate::input::input_manager::{Key, Action};
+
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RGIR";
+const VERSION: u8 = 2;
+
+#[derive(Debug)]
+pub enum RecordingError {
+    IOError(std::io::Error),
+    InvalidFormat(String),
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::IOError(e)       => write!(f, "IOError: {}", e),
+            RecordingError::InvalidFormat(e) => write!(f, "InvalidFormat: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(value: std::io::Error) -> Self {
+        RecordingError::IOError(value)
+    }
+}
+
+/// Keyboard and mouse events of a play session, grouped by the frame they happened in.
+///
+/// Times are in seconds since the recording started, so a replay sees the same timestamps
+/// whenever it runs. Frames without input only store their time. Keys are stored by name, so
+/// recordings stay valid when new keys are added. Gamepads aren't recorded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    /// Sorted by frame number
+    frames: Vec<(u64, Vec<InputEvent>)>,
+    /// Time of every frame, indexed by frame number
+    frame_times: Vec<f64>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the events of `frame` that happened at `time`, events of a frame that was already
+    /// added are appended. Skipped frames get the time of the frame before them.
+    pub fn push_frame(&mut self, frame: u64, time: f64, events: &[InputEvent]) {
+        let index = frame as usize;
+        if self.frame_times.len() <= index {
+            let last_time = self.frame_times.last().copied().unwrap_or(0.0);
+            self.frame_times.resize(index + 1, last_time);
+        }
+        self.frame_times[index] = time;
+
+        let events: Vec<InputEvent> = events.iter()
+            .filter(|event| event.source() != InputSource::Gamepad)
+            .cloned()
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+
+        match self.frames.binary_search_by_key(&frame, |(number, _)| *number) {
+            Ok(index) => self.frames[index].1.extend(events),
+            Err(index) => self.frames.insert(index, (frame, events)),
+        }
+    }
+
+    pub fn get_frame(&self, frame: u64) -> &[InputEvent] {
+        match self.frames.binary_search_by_key(&frame, |(number, _)| *number) {
+            Ok(index) => &self.frames[index].1,
+            Err(_) => &[],
+        }
+    }
+
+    /// Seconds from the start of the recording to `frame`, 0 past the end
+    pub fn get_frame_time(&self, frame: u64) -> f64 {
+        self.frame_times.get(frame as usize).copied().unwrap_or(0.0)
+    }
+
+    /// Number of frames covered, including trailing frames without any input
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_times.len() as u64
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), RecordingError> {
+        std::fs::write(Path::new(path), self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<InputRecording, RecordingError> {
+        let bytes = std::fs::read(Path::new(path))?;
+        InputRecording::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.get_frame_count().to_le_bytes());
+        for time in self.frame_times.iter() {
+            bytes.extend_from_slice(&time.to_le_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.frames.len() as u64).to_le_bytes());
+        for (frame, events) in self.frames.iter() {
+            bytes.extend_from_slice(&frame.to_le_bytes());
+            bytes.extend_from_slice(&(events.len() as u32).to_le_bytes());
+            for event in events {
+                write_event(&mut bytes, event);
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<InputRecording, RecordingError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(RecordingError::InvalidFormat("not an input recording".to_string()));
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(RecordingError::InvalidFormat(format!("unsupported version {}", version)));
+        }
+
+        let mut recording = InputRecording::new();
+        let frame_count = reader.u64()?;
+        for _ in 0..frame_count {
+            recording.frame_times.push(reader.f64()?);
+        }
+
+        // every section is counted, so a cut off file fails instead of losing its last frames
+        let stored_frames = reader.u64()?;
+        for _ in 0..stored_frames {
+            let frame = reader.u64()?;
+            if frame >= frame_count {
+                return Err(RecordingError::InvalidFormat(format!("frame {} is past the end of the recording", frame)));
+            }
+            let count = reader.u32()?;
+            let mut events = Vec::new();
+            for _ in 0..count {
+                events.push(read_event(&mut reader)?);
+            }
+            recording.push_frame(frame, recording.get_frame_time(frame), &events);
+        }
+        if !reader.is_empty() {
+            return Err(RecordingError::InvalidFormat("trailing bytes after the last frame".to_string()));
+        }
+
+        Ok(recording)
+    }
+}
+
+fn write_event(bytes: &mut Vec<u8>, event: &InputEvent) {
+    match event {
+        InputEvent::Key { key, action, modifiers, source, timestamp } => {
+            bytes.push(0);
+            write_key(bytes, *key);
+            bytes.push(match action {
+                Action::Pressed => 0,
+                Action::Released => 1,
+                Action::Held => 2,
+                Action::None => 3,
+            });
+            bytes.push(modifiers_to_bits(*modifiers));
+            bytes.push(match source {
+                InputSource::Keyboard => 0,
+                _ => 1,
+            });
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        InputEvent::KeyRepeat { key, modifiers, timestamp } => {
+            bytes.push(1);
+            write_key(bytes, *key);
+            bytes.push(modifiers_to_bits(*modifiers));
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        InputEvent::Text { text, timestamp } => {
+            bytes.push(2);
+            bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(text.as_bytes());
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        InputEvent::CursorMoved { x, y, timestamp } => {
+            bytes.push(3);
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&y.to_le_bytes());
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        InputEvent::CursorEntered { entered, timestamp } => {
+            bytes.push(4);
+            bytes.push(*entered as u8);
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        InputEvent::Scroll { x, y, timestamp } => {
+            bytes.push(5);
+            bytes.extend_from_slice(&x.to_le_bytes());
+            bytes.extend_from_slice(&y.to_le_bytes());
+            bytes.extend_from_slice(&timestamp.to_le_bytes());
+        },
+        // filtered out in push_frame
+        InputEvent::GamepadConnected { .. }
+            | InputEvent::GamepadDisconnected { .. }
+            | InputEvent::GamepadButton { .. } => {},
+    }
+}
+
+fn read_event(reader: &mut Reader) -> Result<InputEvent, RecordingError> {
+    let tag = reader.u8()?;
+    let event = match tag {
+        0 => {
+            let key = read_key(reader)?;
+            let action = match reader.u8()? {
+                0 => Action::Pressed,
+                1 => Action::Released,
+                2 => Action::Held,
+                _ => Action::None,
+            };
+            let modifiers = modifiers_from_bits(reader.u8()?);
+            let source = match reader.u8()? {
+                0 => InputSource::Keyboard,
+                _ => InputSource::Mouse,
+            };
+            InputEvent::Key { key, action, modifiers, source, timestamp: reader.f64()? }
+        },
+        1 => {
+            let key = read_key(reader)?;
+            let modifiers = modifiers_from_bits(reader.u8()?);
+            InputEvent::KeyRepeat { key, modifiers, timestamp: reader.f64()? }
+        },
+        2 => {
+            let length = reader.u32()? as usize;
+            let text = String::from_utf8(reader.take(length)?.to_vec())
+                .map_err(|e| RecordingError::InvalidFormat(e.to_string()))?;
+            InputEvent::Text { text, timestamp: reader.f64()? }
+        },
+        3 => InputEvent::CursorMoved { x: reader.f64()?, y: reader.f64()?, timestamp: reader.f64()? },
+        4 => InputEvent::CursorEntered { entered: reader.u8()? != 0, timestamp: reader.f64()? },
+        5 => InputEvent::Scroll { x: reader.f64()?, y: reader.f64()?, timestamp: reader.f64()? },
+        _ => return Err(RecordingError::InvalidFormat(format!("unknown event type {}", tag))),
+    };
+    Ok(event)
+}
+
+fn write_key(bytes: &mut Vec<u8>, key: Key) {
+    let name = key.to_string();
+    bytes.push(name.len() as u8);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn read_key(reader: &mut Reader) -> Result<Key, RecordingError> {
+    let length = reader.u8()? as usize;
+    let name = std::str::from_utf8(reader.take(length)?)
+        .map_err(|e| RecordingError::InvalidFormat(e.to_string()))?;
+    name.parse::<Key>().map_err(RecordingError::InvalidFormat)
+}
+
+fn modifiers_to_bits(modifiers: Modifiers) -> u8 {
+    modifiers.shift as u8
+        | (modifiers.control as u8) << 1
+        | (modifiers.alt as u8) << 2
+        | (modifiers.super_key as u8) << 3
+        | (modifiers.caps_lock as u8) << 4
+        | (modifiers.num_lock as u8) << 5
+}
+
+fn modifiers_from_bits(bits: u8) -> Modifiers {
+    Modifiers {
+        shift: bits & 1 != 0,
+        control: bits & (1 << 1) != 0,
+        alt: bits & (1 << 2) != 0,
+        super_key: bits & (1 << 3) != 0,
+        caps_lock: bits & (1 << 4) != 0,
+        num_lock: bits & (1 << 5) != 0,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], RecordingError> {
+        let end = self.offset.checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(RecordingError::InvalidFormat("unexpected end of file".to_string()))?;
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecordingError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, RecordingError> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, RecordingError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn f64(&mut self) -> Result<f64, RecordingError> {
+        Ok(f64::from_bits(self.u64()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gamepad::{GamepadButton, GamepadId};
+
+    fn modifiers() -> Modifiers {
+        Modifiers { shift: true, alt: true, num_lock: true, ..Modifiers::NONE }
+    }
+
+    /// One of every recorded event type, with every key action and source
+    fn sample_recording() -> InputRecording {
+        let mut recording = InputRecording::new();
+        recording.push_frame(0, 0.0, &[
+            InputEvent::Key { key: Key::A, action: Action::Pressed, modifiers: modifiers(), source: InputSource::Keyboard, timestamp: 0.0 },
+            InputEvent::Key { key: Key::MouseLeft, action: Action::Held, modifiers: Modifiers::NONE, source: InputSource::Mouse, timestamp: 0.0 },
+            InputEvent::CursorMoved { x: 12.5, y: -3.25, timestamp: 0.0 },
+            InputEvent::CursorEntered { entered: true, timestamp: 0.0 },
+        ]);
+        recording.push_frame(3, 0.05, &[
+            InputEvent::KeyRepeat { key: Key::Backspace, modifiers: modifiers(), timestamp: 0.049 },
+            InputEvent::Text { text: "é日\n".to_string(), timestamp: 0.049 },
+            InputEvent::Scroll { x: -1.0, y: 0.5, timestamp: 0.05 },
+            InputEvent::Key { key: Key::Unknown(91), action: Action::Released, modifiers: Modifiers::NONE, source: InputSource::Keyboard, timestamp: 0.05 },
+            InputEvent::Key { key: Key::MouseRight, action: Action::None, modifiers: Modifiers::NONE, source: InputSource::Mouse, timestamp: 0.05 },
+            InputEvent::CursorEntered { entered: false, timestamp: 0.05 },
+        ]);
+        // trailing frames without input still count
+        recording.push_frame(5, 0.08, &[]);
+        recording
+    }
+
+    #[test]
+    fn every_event_round_trips() {
+        let recording = sample_recording();
+        let loaded = InputRecording::from_bytes(&recording.to_bytes()).unwrap();
+        assert_eq!(loaded, recording);
+        assert_eq!(loaded.get_frame_count(), 6);
+        assert_eq!(loaded.get_frame(3).len(), 6);
+    }
+
+    #[test]
+    fn skipped_frames_keep_the_previous_time() {
+        let recording = sample_recording();
+        assert_eq!(recording.get_frame_time(2), 0.0);
+        assert_eq!(recording.get_frame_time(3), 0.05);
+        assert_eq!(recording.get_frame_time(4), 0.05);
+        assert_eq!(recording.get_frame_time(5), 0.08);
+        assert_eq!(recording.get_frame_time(6), 0.0);
+    }
+
+    #[test]
+    fn gamepad_events_are_not_recorded() {
+        let mut recording = InputRecording::new();
+        recording.push_frame(0, 0.0, &[
+            InputEvent::GamepadConnected { id: GamepadId::from_index(0), name: "Pad".to_string(), timestamp: 0.0 },
+            InputEvent::GamepadButton { id: GamepadId::from_index(0), button: GamepadButton::A, action: Action::Pressed, timestamp: 0.0 },
+            InputEvent::GamepadDisconnected { id: GamepadId::from_index(0), timestamp: 0.0 },
+        ]);
+        assert!(recording.get_frame(0).is_empty());
+        assert_eq!(InputRecording::from_bytes(&recording.to_bytes()).unwrap(), recording);
+    }
+
+    #[test]
+    fn bad_magic_and_version_are_rejected() {
+        let mut bytes = sample_recording().to_bytes();
+        bytes[0] = b'X';
+        assert!(matches!(InputRecording::from_bytes(&bytes), Err(RecordingError::InvalidFormat(_))));
+
+        let mut bytes = sample_recording().to_bytes();
+        bytes[4] = VERSION + 1;
+        assert!(matches!(InputRecording::from_bytes(&bytes), Err(RecordingError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn truncated_and_padded_files_are_rejected() {
+        let bytes = sample_recording().to_bytes();
+        for length in 0..bytes.len() {
+            assert!(InputRecording::from_bytes(&bytes[..length]).is_err(), "accepted {} of {} bytes", length, bytes.len());
+        }
+
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(InputRecording::from_bytes(&padded).is_err());
+    }
+}
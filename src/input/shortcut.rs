@@ -0,0 +1,272 @@
+use crate::input::input_event::Modifiers;
+use crate::input::input_manager::Key;
+
+/// The key part of a shortcut.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum ShortcutKey {
+    /// A key at a fixed position on the keyboard, whatever the layout prints on it
+    Key(Key),
+    /// Whichever key produces this character in the current layout, so Ctrl+Z is the key
+    /// labelled Z on AZERTY and QWERTZ keyboards as well
+    Character(char),
+}
+
+/// A key combined with modifiers, such as Ctrl+Shift+S.
+///
+/// Modifiers have to match exactly, Ctrl+S doesn't trigger while Shift is also held. Caps lock
+/// and num lock are ignored.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Shortcut {
+    pub control: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub super_key: bool,
+    pub key: ShortcutKey,
+}
+
+impl Shortcut {
+    /// A shortcut on a physical key
+    pub fn new(modifiers: Modifiers, key: Key) -> Self {
+        Self::with_key(modifiers, ShortcutKey::Key(key))
+    }
+
+    /// A shortcut on whichever key produces `character` in the current layout
+    pub fn character(modifiers: Modifiers, character: char) -> Self {
+        Self::with_key(modifiers, ShortcutKey::Character(character.to_lowercase().next().unwrap_or(character)))
+    }
+
+    fn with_key(modifiers: Modifiers, key: ShortcutKey) -> Self {
+        Self {
+            control: modifiers.control,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+            super_key: modifiers.super_key,
+            key,
+        }
+    }
+
+    /// Parses shortcuts such as "Ctrl+Shift+S", "Alt+F4" or "Ctrl++". A single character is
+    /// matched by layout, longer names such as "Enter" or "ArrowUp" are physical keys. Physical
+    /// letter keys are written "KeyA" to "KeyZ", the way `Display` formats them.
+    ///
+    /// Modifiers are Ctrl/Control, Shift, Alt/Option and Super/Cmd/Command/Meta/Win, in any
+    /// order and case.
+    pub fn parse(text: &str) -> Result<Shortcut, String> {
+        Self::parse_with(text, false)
+    }
+
+    /// Like `parse` but single characters are physical keys on a US layout, useful for
+    /// shortcuts that should stay in the same place such as WASD
+    pub fn parse_physical(text: &str) -> Result<Shortcut, String> {
+        Self::parse_with(text, true)
+    }
+
+    fn parse_with(text: &str, is_physical: bool) -> Result<Shortcut, String> {
+        let text = text.trim();
+        // the key itself can be "+", as in "Ctrl++"
+        let (modifier_text, key_text) = match text.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => (rest.strip_suffix('+').unwrap_or(rest), "+"),
+            _ => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        let mut modifiers = Modifiers::NONE;
+        for modifier in modifier_text.split('+').map(str::trim).filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.control = true,
+                "shift" => modifiers.shift = true,
+                "alt" | "option" => modifiers.alt = true,
+                "super" | "cmd" | "command" | "meta" | "win" => modifiers.super_key = true,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, text)),
+            }
+        }
+
+        let key_text = key_text.trim();
+        let mut chars = key_text.chars();
+        let key = match (chars.next(), chars.next()) {
+            (None, _) => return Err(format!("missing key in '{}'", text)),
+            (Some(character), None) if is_physical => match Key::from_char(character) {
+                Some(key) => ShortcutKey::Key(key),
+                None => return Err(format!("no physical key for '{}'", character)),
+            },
+            (Some(character), None) => return Ok(Self::character(modifiers, character)),
+            _ => ShortcutKey::Key(parse_key_name(key_text)?),
+        };
+
+        Ok(Self::with_key(modifiers, key))
+    }
+
+    /// True if pressing `key` while `modifiers` are held triggers this shortcut. `key_name` is
+    /// the character the key produces in the current layout, without it the US layout is
+    /// assumed.
+    pub fn matches(&self, key: Key, modifiers: Modifiers, key_name: Option<&str>) -> bool {
+        if self.control != modifiers.control
+            || self.shift != modifiers.shift
+            || self.alt != modifiers.alt
+            || self.super_key != modifiers.super_key
+        {
+            return false;
+        }
+
+        match self.key {
+            ShortcutKey::Key(shortcut_key) => shortcut_key == key,
+            ShortcutKey::Character(character) => match key_name {
+                Some(name) => {
+                    let mut name = name.chars().flat_map(char::to_lowercase);
+                    name.next() == Some(character) && name.next().is_none()
+                },
+                None => Key::from_char(character) == Some(key),
+            },
+        }
+    }
+}
+
+fn parse_key_name(name: &str) -> Result<Key, String> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "esc" => Key::Escape,
+        "del" => Key::Delete,
+        "ins" => Key::Insert,
+        "return" => Key::Enter,
+        "up" => Key::ArrowUp,
+        "down" => Key::ArrowDown,
+        "left" => Key::ArrowLeft,
+        "right" => Key::ArrowRight,
+        "pgup" => Key::PageUp,
+        "pgdn" => Key::PageDown,
+        lower => match lower.strip_prefix("key").and_then(physical_letter) {
+            Some(key) => key,
+            None => return name.parse::<Key>(),
+        },
+    };
+    Ok(key)
+}
+
+/// The letter key of "a" to "z"
+fn physical_letter(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Key::from_char(letter),
+        _ => None,
+    }
+}
+
+impl std::str::FromStr for Shortcut {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Shortcut::parse(s)
+    }
+}
+
+/// Formats the shortcut for menus and key hints, e.g. "Ctrl+Shift+S". Physical letter keys
+/// are written "KeyA" so that `parse` reads every formatted shortcut back as the same one.
+impl std::fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let modifiers = [(self.control, "Ctrl"), (self.shift, "Shift"), (self.alt, "Alt"), (self.super_key, "Super")];
+        for (_, name) in modifiers.iter().filter(|(is_held, _)| *is_held) {
+            write!(f, "{}+", name)?;
+        }
+        match self.key {
+            ShortcutKey::Key(key) => {
+                let name = key.to_string();
+                match physical_letter(&name) {
+                    Some(_) => write!(f, "Key{}", name),
+                    None => write!(f, "{}", name),
+                }
+            },
+            ShortcutKey::Character(character) => {
+                // some characters such as ß turn into several when uppercased
+                let mut upper = character.to_uppercase();
+                match (upper.next(), upper.next()) {
+                    (Some(upper), None) => write!(f, "{}", upper),
+                    _ => write!(f, "{}", character),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTROL: Modifiers = Modifiers { control: true, ..Modifiers::NONE };
+    const CONTROL_SHIFT: Modifiers = Modifiers { control: true, shift: true, ..Modifiers::NONE };
+
+    #[test]
+    fn parses_modifiers_and_keys() {
+        assert_eq!(Shortcut::parse("Ctrl+Shift+S"), Ok(Shortcut::character(CONTROL_SHIFT, 's')));
+        assert_eq!(Shortcut::parse(" shift + control + s "), Ok(Shortcut::character(CONTROL_SHIFT, 's')));
+        assert_eq!(Shortcut::parse("Alt+F4"), Ok(Shortcut::new(Modifiers { alt: true, ..Modifiers::NONE }, Key::F4)));
+        assert_eq!(Shortcut::parse("Cmd+Return"), Ok(Shortcut::new(Modifiers { super_key: true, ..Modifiers::NONE }, Key::Enter)));
+        assert_eq!(Shortcut::parse("Ctrl+KeyA"), Ok(Shortcut::new(CONTROL, Key::A)));
+        assert_eq!(Shortcut::parse_physical("Ctrl+A"), Ok(Shortcut::new(CONTROL, Key::A)));
+    }
+
+    #[test]
+    fn plus_can_be_the_key() {
+        assert_eq!(Shortcut::parse("Ctrl++"), Ok(Shortcut::character(CONTROL, '+')));
+        assert_eq!(Shortcut::parse("Ctrl+Shift++"), Ok(Shortcut::character(CONTROL_SHIFT, '+')));
+        assert_eq!(Shortcut::parse("+"), Ok(Shortcut::character(Modifiers::NONE, '+')));
+    }
+
+    #[test]
+    fn invalid_shortcuts_are_rejected() {
+        assert!(Shortcut::parse("Hyper+S").is_err());
+        assert!(Shortcut::parse("Ctrl+").is_err());
+        assert!(Shortcut::parse("").is_err());
+        assert!(Shortcut::parse("Ctrl+NotAKey").is_err());
+        assert!(Shortcut::parse("Ctrl+KeyAB").is_err());
+        assert!(Shortcut::parse_physical("Ctrl+é").is_err());
+    }
+
+    #[test]
+    fn formatted_shortcuts_parse_back() {
+        let shortcuts = [
+            Shortcut::character(CONTROL_SHIFT, 's'),
+            Shortcut::character(CONTROL, '+'),
+            Shortcut::character(CONTROL, 'ß'),
+            Shortcut::new(CONTROL, Key::A),
+            Shortcut::new(Modifiers { alt: true, super_key: true, ..Modifiers::NONE }, Key::F4),
+            Shortcut::new(Modifiers::NONE, Key::Number1),
+            Shortcut::new(CONTROL, Key::Unknown(93)),
+        ];
+        for shortcut in shortcuts {
+            assert_eq!(shortcut.to_string().parse::<Shortcut>(), Ok(shortcut), "{}", shortcut);
+        }
+        assert_eq!(Shortcut::character(CONTROL_SHIFT, 's').to_string(), "Ctrl+Shift+S");
+        assert_eq!(Shortcut::new(CONTROL, Key::A).to_string(), "Ctrl+KeyA");
+    }
+
+    #[test]
+    fn modifiers_match_exactly() {
+        let save = Shortcut::parse("Ctrl+S").unwrap();
+        assert!(save.matches(Key::S, CONTROL, None));
+        assert!(!save.matches(Key::S, CONTROL_SHIFT, None));
+        assert!(!save.matches(Key::S, Modifiers::NONE, None));
+        // lock keys don't count
+        assert!(save.matches(Key::S, Modifiers { caps_lock: true, num_lock: true, ..CONTROL }, None));
+    }
+
+    #[test]
+    fn characters_follow_the_layout() {
+        let select_all = Shortcut::parse("Ctrl+A").unwrap();
+        let undo = Shortcut::parse("Ctrl+Z").unwrap();
+
+        // AZERTY has A where QWERTY has Q
+        assert!(select_all.matches(Key::Q, CONTROL, Some("a")));
+        assert!(!select_all.matches(Key::A, CONTROL, Some("q")));
+        // QWERTZ swaps Y and Z
+        assert!(undo.matches(Key::Y, CONTROL, Some("z")));
+        assert!(undo.matches(Key::Y, CONTROL, Some("Z")));
+        assert!(!undo.matches(Key::Z, CONTROL, Some("y")));
+        // without a name the US layout is assumed
+        assert!(undo.matches(Key::Z, CONTROL, None));
+    }
+
+    #[test]
+    fn physical_keys_ignore_the_layout() {
+        let forward = Shortcut::parse_physical("W").unwrap();
+        assert!(forward.matches(Key::W, Modifiers::NONE, Some("z")));
+        assert!(!forward.matches(Key::Z, Modifiers::NONE, Some("w")));
+    }
+}
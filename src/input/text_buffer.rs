@@ -0,0 +1,441 @@
+use crate::input::input_event::InputEvent;
+use crate::input::input_manager::{Key, Action};
+
+/// Editing model for a single line text field such as a name entry box or a chat input.
+///
+/// It doesn't need a window, feed it the events from `Engine::drain_input_events` with
+/// `handle_event` or call the editing methods directly. Positions are byte offsets into the
+/// text and always sit on a char boundary.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextBuffer {
+    text: String,
+    cursor: usize,
+    /// The other end of the selection, the selection spans from here to `cursor`
+    anchor: Option<usize>,
+    /// Maximum number of chars, None for no limit
+    max_length: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Whitespace,
+    Word,
+    Punctuation,
+}
+
+fn char_class(character: char) -> CharClass {
+    if character.is_whitespace() {
+        CharClass::Whitespace
+    }
+    else if character.is_alphanumeric() || character == '_' {
+        CharClass::Word
+    }
+    else {
+        CharClass::Punctuation
+    }
+}
+
+impl TextBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts with `text` and the cursor at the end
+    pub fn with_text(text: &str) -> Self {
+        let mut buffer = Self::new();
+        buffer.set_text(text);
+        buffer
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the cursor to the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.cursor = 0;
+        self.anchor = None;
+        self.insert(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    pub fn get_cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Moves the cursor to the byte offset `position`, snapped back to the nearest char boundary
+    pub fn set_cursor(&mut self, position: usize, extend_selection: bool) {
+        let mut position = position.min(self.text.len());
+        while !self.text.is_char_boundary(position) {
+            position -= 1;
+        }
+        self.move_to(position, extend_selection);
+    }
+
+    pub fn get_max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Limits the text to `max_length` chars, existing text past the limit is cut off
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+        if let Some((end, _)) = max_length.and_then(|max| self.text.char_indices().nth(max)) {
+            self.text.truncate(end);
+            self.cursor = self.cursor.min(end);
+            self.anchor = self.anchor.map(|anchor| anchor.min(end));
+        }
+    }
+
+    /// Start and end of the selection as byte offsets, None if nothing is selected
+    pub fn get_selection(&self) -> Option<(usize, usize)> {
+        match self.anchor {
+            Some(anchor) if anchor != self.cursor => Some((anchor.min(self.cursor), anchor.max(self.cursor))),
+            _ => None,
+        }
+    }
+
+    pub fn get_selected_text(&self) -> Option<&str> {
+        self.get_selection().map(|(start, end)| &self.text[start..end])
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection. Control characters such as new
+    /// lines are dropped and the text is cut short if it would go over the max length.
+    pub fn insert(&mut self, text: &str) {
+        self.delete_selection();
+
+        let mut remaining = match self.max_length {
+            Some(max) => max.saturating_sub(self.text.chars().count()),
+            None => usize::MAX,
+        };
+        for character in text.chars().filter(|character| !character.is_control()) {
+            if remaining == 0 {
+                break;
+            }
+            self.text.insert(self.cursor, character);
+            self.cursor += character.len_utf8();
+            remaining -= 1;
+        }
+    }
+
+    /// Removes the selected text and returns it, used for cut
+    pub fn cut_selection(&mut self) -> Option<String> {
+        let selected = self.get_selected_text()?.to_string();
+        self.delete_selection();
+        Some(selected)
+    }
+
+    /// Deletes the selection, or the char before the cursor
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() {
+            let start = self.previous_boundary(self.cursor);
+            self.delete_range(start, self.cursor);
+        }
+    }
+
+    /// Deletes the selection, or the char after the cursor
+    pub fn delete(&mut self) {
+        if !self.delete_selection() {
+            let end = self.next_boundary(self.cursor);
+            self.delete_range(self.cursor, end);
+        }
+    }
+
+    /// Deletes the selection, or everything back to the start of the previous word
+    pub fn backspace_word(&mut self) {
+        if !self.delete_selection() {
+            let start = self.previous_word_boundary(self.cursor);
+            self.delete_range(start, self.cursor);
+        }
+    }
+
+    /// Deletes the selection, or everything up to the start of the next word
+    pub fn delete_word(&mut self) {
+        if !self.delete_selection() {
+            let end = self.next_word_boundary(self.cursor);
+            self.delete_range(self.cursor, end);
+        }
+    }
+
+    /// Moves one char to the left. Without `extend_selection` a selection collapses to its
+    /// start instead.
+    pub fn move_left(&mut self, extend_selection: bool) {
+        match self.get_selection() {
+            Some((start, _)) if !extend_selection => self.move_to(start, false),
+            _ => self.move_to(self.previous_boundary(self.cursor), extend_selection),
+        }
+    }
+
+    pub fn move_right(&mut self, extend_selection: bool) {
+        match self.get_selection() {
+            Some((_, end)) if !extend_selection => self.move_to(end, false),
+            _ => self.move_to(self.next_boundary(self.cursor), extend_selection),
+        }
+    }
+
+    /// Jumps to the start of the current or previous word
+    pub fn move_word_left(&mut self, extend_selection: bool) {
+        self.move_to(self.previous_word_boundary(self.cursor), extend_selection);
+    }
+
+    /// Jumps past the end of the current word and the whitespace after it
+    pub fn move_word_right(&mut self, extend_selection: bool) {
+        self.move_to(self.next_word_boundary(self.cursor), extend_selection);
+    }
+
+    pub fn move_home(&mut self, extend_selection: bool) {
+        self.move_to(0, extend_selection);
+    }
+
+    pub fn move_end(&mut self, extend_selection: bool) {
+        self.move_to(self.text.len(), extend_selection);
+    }
+
+    /// Applies typed text and editing keys. Shift extends the selection, Control moves and
+    /// deletes whole words and Control+A selects everything. Returns true if the event was used.
+    ///
+    /// Clipboard shortcuts need the window, handle them with `Engine::clipboard_text` and
+    /// `Engine::set_clipboard_text` together with `get_selected_text`, `cut_selection` and
+    /// `insert`.
+    pub fn handle_event(&mut self, event: &InputEvent) -> bool {
+        let (key, modifiers) = match event {
+            InputEvent::Text { text, .. } => {
+                self.insert(text);
+                return true;
+            },
+            InputEvent::Key { key, action: Action::Pressed, modifiers, .. } => (*key, *modifiers),
+            InputEvent::KeyRepeat { key, modifiers, .. } => (*key, *modifiers),
+            _ => return false,
+        };
+
+        let select = modifiers.shift;
+        match (key, modifiers.control) {
+            (Key::Backspace, false) => self.backspace(),
+            (Key::Backspace, true) => self.backspace_word(),
+            (Key::Delete, false) => self.delete(),
+            (Key::Delete, true) => self.delete_word(),
+            (Key::ArrowLeft, false) => self.move_left(select),
+            (Key::ArrowLeft, true) => self.move_word_left(select),
+            (Key::ArrowRight, false) => self.move_right(select),
+            (Key::ArrowRight, true) => self.move_word_right(select),
+            (Key::Home, _) => self.move_home(select),
+            (Key::End, _) => self.move_end(select),
+            (Key::A, true) => self.select_all(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn move_to(&mut self, position: usize, extend_selection: bool) {
+        if extend_selection {
+            self.anchor.get_or_insert(self.cursor);
+        }
+        else {
+            self.anchor = None;
+        }
+        self.cursor = position;
+    }
+
+    /// Returns false if nothing was selected
+    fn delete_selection(&mut self) -> bool {
+        match self.get_selection() {
+            Some((start, end)) => {
+                self.delete_range(start, end);
+                true
+            },
+            None => {
+                self.anchor = None;
+                false
+            },
+        }
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+        self.anchor = None;
+    }
+
+    fn previous_boundary(&self, position: usize) -> usize {
+        self.text[..position].char_indices().next_back().map(|(index, _)| index).unwrap_or(0)
+    }
+
+    fn next_boundary(&self, position: usize) -> usize {
+        self.text[position..].chars().next().map(|character| position + character.len_utf8()).unwrap_or(position)
+    }
+
+    fn previous_word_boundary(&self, position: usize) -> usize {
+        let mut chars = self.text[..position].char_indices().rev().peekable();
+        // skip the whitespace before the cursor, then the run of chars of the same kind
+        while chars.next_if(|(_, character)| char_class(*character) == CharClass::Whitespace).is_some() {}
+        let Some(&(_, first)) = chars.peek() else {
+            return 0;
+        };
+        let class = char_class(first);
+        let mut boundary = position;
+        while let Some((index, _)) = chars.next_if(|(_, character)| char_class(*character) == class) {
+            boundary = index;
+        }
+        boundary
+    }
+
+    fn next_word_boundary(&self, position: usize) -> usize {
+        let mut chars = self.text[position..].char_indices().peekable();
+        if let Some(&(_, first)) = chars.peek() {
+            let class = char_class(first);
+            if class != CharClass::Whitespace {
+                while chars.next_if(|(_, character)| char_class(*character) == class).is_some() {}
+            }
+        }
+        while chars.next_if(|(_, character)| char_class(*character) == CharClass::Whitespace).is_some() {}
+        chars.peek().map(|(index, _)| position + index).unwrap_or(self.text.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::input_event::{InputSource, Modifiers};
+
+    #[test]
+    fn multi_byte_insert_and_delete() {
+        let mut buffer = TextBuffer::with_text("aé");
+        buffer.insert("日本");
+        assert_eq!(buffer.get_text(), "aé日本");
+        assert_eq!(buffer.get_cursor(), "aé日本".len());
+
+        buffer.backspace();
+        assert_eq!(buffer.get_text(), "aé日");
+        buffer.move_home(false);
+        buffer.move_right(false);
+        buffer.delete();
+        assert_eq!(buffer.get_text(), "a日");
+        assert_eq!(buffer.get_cursor(), 1);
+    }
+
+    #[test]
+    fn cursor_moves_by_whole_chars() {
+        let mut buffer = TextBuffer::with_text("é😀x");
+        buffer.move_left(false);
+        assert_eq!(buffer.get_cursor(), "é😀".len());
+        buffer.move_left(false);
+        assert_eq!(buffer.get_cursor(), "é".len());
+        buffer.move_left(false);
+        buffer.move_left(false);
+        assert_eq!(buffer.get_cursor(), 0);
+        buffer.move_right(false);
+        assert_eq!(buffer.get_cursor(), "é".len());
+
+        // positions inside a char snap back to its start
+        buffer.set_cursor("é".len() + 2, false);
+        assert_eq!(buffer.get_cursor(), "é".len());
+        buffer.set_cursor(100, false);
+        assert_eq!(buffer.get_cursor(), "é😀x".len());
+    }
+
+    #[test]
+    fn insert_replaces_selection() {
+        let mut buffer = TextBuffer::with_text("hello wörld");
+        buffer.move_word_left(false);
+        buffer.move_end(true);
+        assert_eq!(buffer.get_selected_text(), Some("wörld"));
+
+        buffer.insert("thére");
+        assert_eq!(buffer.get_text(), "hello thére");
+        assert_eq!(buffer.get_selection(), None);
+
+        buffer.select_all();
+        assert_eq!(buffer.cut_selection(), Some("hello thére".to_string()));
+        assert_eq!(buffer.get_text(), "");
+    }
+
+    #[test]
+    fn selection_collapses_on_move() {
+        let mut buffer = TextBuffer::with_text("abcd");
+        buffer.move_left(true);
+        buffer.move_left(true);
+        assert_eq!(buffer.get_selection(), Some((2, 4)));
+        buffer.move_left(false);
+        assert_eq!((buffer.get_selection(), buffer.get_cursor()), (None, 2));
+    }
+
+    #[test]
+    fn backspace_and_delete_at_the_edges() {
+        let mut buffer = TextBuffer::with_text("ab");
+        buffer.delete();
+        assert_eq!(buffer.get_text(), "ab");
+
+        buffer.move_home(false);
+        buffer.backspace();
+        assert_eq!((buffer.get_text(), buffer.get_cursor()), ("ab", 0));
+
+        buffer.move_right(true);
+        buffer.backspace();
+        assert_eq!((buffer.get_text(), buffer.get_cursor()), ("b", 0));
+
+        let mut empty = TextBuffer::new();
+        empty.backspace();
+        empty.delete();
+        empty.backspace_word();
+        empty.delete_word();
+        assert_eq!((empty.get_text(), empty.get_cursor()), ("", 0));
+    }
+
+    #[test]
+    fn word_deletion() {
+        let mut buffer = TextBuffer::with_text("let x = föo_bar;  ");
+        buffer.backspace_word();
+        assert_eq!(buffer.get_text(), "let x = föo_bar");
+        buffer.backspace_word();
+        assert_eq!(buffer.get_text(), "let x = ");
+        buffer.move_home(false);
+        buffer.delete_word();
+        assert_eq!(buffer.get_text(), "x = ");
+    }
+
+    #[test]
+    fn max_length_counts_chars() {
+        let mut buffer = TextBuffer::new();
+        buffer.set_max_length(Some(3));
+        buffer.insert("日本語です");
+        assert_eq!(buffer.get_text(), "日本語");
+        buffer.insert("x\n");
+        assert_eq!(buffer.get_text(), "日本語");
+    }
+
+    #[test]
+    fn handles_key_and_text_events() {
+        let key = |key, modifiers| InputEvent::Key {
+            key,
+            action: Action::Pressed,
+            modifiers,
+            source: InputSource::Keyboard,
+            timestamp: 0.0,
+        };
+        let shift = Modifiers { shift: true, ..Modifiers::NONE };
+        let control = Modifiers { control: true, ..Modifiers::NONE };
+
+        let mut buffer = TextBuffer::new();
+        assert!(buffer.handle_event(&InputEvent::Text { text: "añb".to_string(), timestamp: 0.0 }));
+        assert!(buffer.handle_event(&key(Key::ArrowLeft, shift)));
+        assert!(buffer.handle_event(&key(Key::ArrowLeft, shift)));
+        assert_eq!(buffer.get_selected_text(), Some("ñb"));
+        assert!(buffer.handle_event(&key(Key::Backspace, Modifiers::NONE)));
+        assert_eq!(buffer.get_text(), "a");
+        assert!(buffer.handle_event(&key(Key::A, control)));
+        assert_eq!(buffer.get_selected_text(), Some("a"));
+        assert!(!buffer.handle_event(&key(Key::Escape, Modifiers::NONE)));
+    }
+}
@@ -6,7 +6,31 @@ mod utility;
 
 pub use engine::Engine;
 pub use input::input_manager::{Key, Action};
+pub use input::input_event::{InputEvent, InputSource, Modifiers};
+pub use input::action_map::{ActionMap, ActionMapError};
+pub use input::text_buffer::TextBuffer;
+pub use input::recording::{InputRecording, RecordingError};
+pub use input::shortcut::{Shortcut, ShortcutKey};
+pub use input::gesture::{Gesture, GestureRecognizer, GestureSettings};
+pub use input::gamepad::{
+    GamepadId, GamepadButton, GamepadAxis, GamepadSnapshot, GamepadError,
+    GamepadBackend, GlfwGamepadBackend, SimulatedGamepadBackend,
+};
 pub use video::color::Color;
+pub use video::window_event::WindowEvent;
+pub use video::monitor::{WindowMode, VideoMode, MonitorInfo};
+pub use video::cursor::{CursorMode, CursorShape};
+pub use video::camera::{Camera2D, CameraId, ViewTransform};
+pub use video::window::{FrameStats, WindowError};
+pub use video::framebuffer::count_mismatched_pixels;
 pub use video::sprite::{Sprite, Flip, SpriteSheet, SpriteId, SpriteSheetId};
 pub use video::shader_manager::{ShaderId, FragmentShader, VertexShader};
 pub use utility::timer::Timer;
+pub use audio::audio_manager::{AudioManager, AudioError, SoundId};
+pub use audio::backend::{AudioBackend, DeviceBackend, OfflineBackend};
+pub use audio::mixer::{SoundBuffer, VoiceHandle, VoiceId, VoiceSettings, VoiceStealing};
+pub use audio::music::MusicTrack;
+pub use audio::bus::BusId;
+pub use audio::spatial::{Emitter, Rolloff, SpatialSettings};
+pub use audio::synth::{SynthParams, Waveform};
+pub use audio::effects::{Effect, LowPassFilter, HighPassFilter, Reverb, Compressor};
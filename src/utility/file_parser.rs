@@ -1,4 +1,7 @@
+use crate::audio::audio_manager::AudioError;
+
 use image::ImageError;
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
 pub fn get_rbga_from_image(path: &str) -> Result<(u32, u32, Vec<u8>), ImageError> {
     match image::open(path) {
@@ -11,3 +14,176 @@ pub fn get_rbga_from_image(path: &str) -> Result<(u32, u32, Vec<u8>), ImageError
         Err(e) => Err(e)
     }
 }
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Stream description read from the fmt chunk of a WAV file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WavFormat {
+    pub format_tag: u16,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+}
+
+impl WavFormat {
+    pub fn bytes_per_frame(&self) -> usize {
+        self.channels as usize * (self.bits_per_sample as usize / 8)
+    }
+}
+
+/// Reads a WAV file and returns (sample_rate, channels, samples) where samples are interleaved
+/// and normalized to the range [-1.0, 1.0].
+pub fn get_samples_from_wav(path: &str) -> Result<(u32, u16, Vec<f32>), AudioError> {
+    let bytes = std::fs::read(path)?;
+    decode_wav(&bytes).map_err(|e| match e {
+        AudioError::InvalidFormat(e) => AudioError::InvalidFormat(format!("{}: {}", path, e)),
+        AudioError::UnsupportedFormat(e) => AudioError::UnsupportedFormat(format!("{}: {}", path, e)),
+        e => e,
+    })
+}
+
+pub fn decode_wav(bytes: &[u8]) -> Result<(u32, u16, Vec<f32>), AudioError> {
+    let mut cursor = Cursor::new(bytes);
+    let (format, data_offset, data_length) = read_wav_header(&mut cursor)?;
+    let start = data_offset as usize;
+    let end = (start + data_length as usize).min(bytes.len());
+    let samples = decode_wav_samples(&format, &bytes[start..end])?;
+    Ok((format.sample_rate, format.channels, samples))
+}
+
+/// Walks the chunks of a WAV file and returns the format along with the (offset, length) in
+/// bytes of the sample data, so the samples can be read in pieces.
+pub fn read_wav_header<R: Read + Seek>(reader: &mut R) -> Result<(WavFormat, u64, u64), AudioError> {
+    let mut header = [0u8; 12];
+    if reader.read_exact(&mut header).is_err() || &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(AudioError::InvalidFormat("Missing RIFF/WAVE header".to_string()));
+    }
+
+    let mut format: Option<WavFormat> = None;
+    let mut chunk_header = [0u8; 8];
+    while reader.read_exact(&mut chunk_header).is_ok() {
+        let chunk_size = read_u32(&chunk_header, 4) as u64;
+
+        match &chunk_header[0..4] {
+            b"fmt " => {
+                if chunk_size < 16 {
+                    return Err(AudioError::InvalidFormat("fmt chunk is too small".to_string()));
+                }
+                let mut chunk = vec![0u8; chunk_size as usize];
+                reader.read_exact(&mut chunk)
+                    .map_err(|_| AudioError::InvalidFormat("fmt chunk is truncated".to_string()))?;
+
+                let mut format_tag = read_u16(&chunk, 0);
+                // The real format of an extensible file is the first two bytes of the sub format GUID
+                if format_tag == WAVE_FORMAT_EXTENSIBLE && chunk.len() >= 26 {
+                    format_tag = read_u16(&chunk, 24);
+                }
+                format = Some(WavFormat {
+                    format_tag,
+                    channels: read_u16(&chunk, 2),
+                    sample_rate: read_u32(&chunk, 4),
+                    bits_per_sample: read_u16(&chunk, 14),
+                });
+                // chunks are padded to an even number of bytes
+                reader.seek(SeekFrom::Current((chunk_size & 1) as i64))?;
+            },
+            b"data" => {
+                let format = format.ok_or(AudioError::InvalidFormat("data chunk before fmt chunk".to_string()))?;
+                validate_wav_format(&format)?;
+                let data_offset = reader.stream_position()?;
+                let data_end = reader.seek(SeekFrom::End(0))?;
+                reader.seek(SeekFrom::Start(data_offset))?;
+                return Ok((format, data_offset, chunk_size.min(data_end - data_offset)));
+            },
+            _ => {
+                reader.seek(SeekFrom::Current((chunk_size + (chunk_size & 1)) as i64))?;
+            },
+        }
+    }
+
+    match format {
+        Some(_) => Err(AudioError::InvalidFormat("Missing data chunk".to_string())),
+        None => Err(AudioError::InvalidFormat("Missing fmt chunk".to_string())),
+    }
+}
+
+fn validate_wav_format(format: &WavFormat) -> Result<(), AudioError> {
+    if format.channels == 0 || format.sample_rate == 0 {
+        return Err(AudioError::InvalidFormat(
+            format!("Invalid stream description: channels={}, sample_rate={}", format.channels, format.sample_rate)
+        ));
+    }
+
+    match (format.format_tag, format.bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8 | 16 | 24 | 32) | (WAVE_FORMAT_IEEE_FLOAT, 32) => Ok(()),
+        (tag, bits) => Err(AudioError::UnsupportedFormat(
+            format!("Unsupported WAV encoding: format={}, bits_per_sample={}", tag, bits)
+        )),
+    }
+}
+
+/// Converts raw sample data of the given format into normalized f32 samples.
+pub fn decode_wav_samples(format: &WavFormat, data: &[u8]) -> Result<Vec<f32>, AudioError> {
+    validate_wav_format(format)?;
+
+    let samples: Vec<f32> = match (format.format_tag, format.bits_per_sample) {
+        (WAVE_FORMAT_PCM, 8) => data.iter()
+            .map(|&s| (s as f32 - 128.0) / 128.0)
+            .collect(),
+        (WAVE_FORMAT_PCM, 16) => data.chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32_768.0)
+            .collect(),
+        (WAVE_FORMAT_PCM, 24) => data.chunks_exact(3)
+            .map(|s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8_388_608.0)
+            .collect(),
+        (WAVE_FORMAT_PCM, _) => data.chunks_exact(4)
+            .map(|s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        _ => data.chunks_exact(4)
+            .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+            .collect(),
+    };
+
+    Ok(samples)
+}
+
+/// Writes interleaved samples in the range [-1.0, 1.0] as a 16 bit PCM WAV file.
+pub fn save_samples_to_wav(path: &str, sample_rate: u32, channels: u16, samples: &[f32]) -> Result<(), AudioError> {
+    let data_length = (samples.len() * 2) as u32;
+    let block_align = channels * 2;
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(44 + data_length as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_length).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+    bytes.extend_from_slice(&channels.to_le_bytes());
+    bytes.extend_from_slice(&sample_rate.to_le_bytes());
+    bytes.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    bytes.extend_from_slice(&block_align.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_length.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    std::fs::write(path, bytes)?;
+    Ok(())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
@@ -0,0 +1,354 @@
+use crate::video::sprite::SpriteSheetError;
+
+use gl::types::GLuint;
+
+const DEFAULT_PAGE_SIZE: u32 = 2048;
+/// Every image gets its edge pixels copied around it, so linear filtering doesn't pick up the
+/// neighbouring image in the page
+const PADDING: u32 = 1;
+
+/// Packs rectangles into a fixed area by keeping track of the top edge of what has been placed,
+/// each new rectangle goes where it ends up lowest.
+#[derive(Clone, Debug)]
+pub struct SkylinePacker {
+    width: u32,
+    height: u32,
+    /// (x, y, width) of each segment of the skyline, sorted by x and covering the full width
+    skyline: Vec<(u32, u32, u32)>,
+}
+
+impl SkylinePacker {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            skyline: vec![(0, 0, width)],
+        }
+    }
+
+    /// Finds room for a `width` by `height` rectangle and returns its top left corner, None
+    /// if it doesn't fit anymore
+    pub fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let mut best: Option<(usize, u32, u32)> = None;
+        for index in 0..self.skyline.len() {
+            let Some(y) = self.fit(index, width, height) else {
+                continue;
+            };
+            let x = self.skyline[index].0;
+            if best.is_none_or(|(_, best_x, best_y)| (y, x) < (best_y, best_x)) {
+                best = Some((index, x, y));
+            }
+        }
+
+        let (index, x, y) = best?;
+        self.place(index, x, y + height, width);
+        Some((x, y))
+    }
+
+    /// Height the rectangle would sit at when its left edge is at the start of segment `index`
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.skyline[index].0;
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut covered = 0;
+        for &(_, segment_y, segment_width) in self.skyline[index..].iter() {
+            y = y.max(segment_y);
+            if y + height > self.height {
+                return None;
+            }
+            covered += segment_width;
+            if covered >= width {
+                break;
+            }
+        }
+        Some(y)
+    }
+
+    fn place(&mut self, index: usize, x: u32, y: u32, width: u32) {
+        self.skyline.insert(index, (x, y, width));
+
+        // cut away the segments the new one now covers
+        let end = x + width;
+        let next = index + 1;
+        while next < self.skyline.len() {
+            let (segment_x, segment_y, segment_width) = self.skyline[next];
+            if segment_x >= end {
+                break;
+            }
+            if segment_x + segment_width <= end {
+                self.skyline.remove(next);
+            }
+            else {
+                self.skyline[next] = (end, segment_y, segment_x + segment_width - end);
+                break;
+            }
+        }
+
+        // merge neighbours at the same height
+        let mut merged: Vec<(u32, u32, u32)> = Vec::with_capacity(self.skyline.len());
+        for segment in self.skyline.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.1 == segment.1 => last.2 += segment.2,
+                _ => merged.push(segment),
+            }
+        }
+        self.skyline = merged;
+    }
+}
+
+/// Where an image ended up in the atlas
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AtlasRegion {
+    pub texture: GLuint,
+    /// Top left corner of the image in the page, without padding
+    pub x: u32,
+    pub y: u32,
+    pub page_width: u32,
+    pub page_height: u32,
+}
+
+impl AtlasRegion {
+    /// (u_min, v_min, u_max, v_max) of a rectangle inside the image, in the order
+    /// `SpriteSheet::get_uv` uses with v_min at the bottom
+    pub fn get_uv(&self, x: u32, y: u32, width: u32, height: u32) -> (f32, f32, f32, f32) {
+        let left = (self.x + x) as f32 / self.page_width as f32;
+        let right = (self.x + x + width) as f32 / self.page_width as f32;
+        let top = (self.y + y) as f32 / self.page_height as f32;
+        let bottom = (self.y + y + height) as f32 / self.page_height as f32;
+        (left, bottom, right, top)
+    }
+}
+
+struct AtlasPage {
+    texture: GLuint,
+    width: u32,
+    height: u32,
+    packer: SkylinePacker,
+}
+
+impl Drop for AtlasPage {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
+
+/// Shared textures that sprite sheets and quad colors are packed into, so sprites using
+/// different sheets can still be drawn together. Images too big for a page get a page of their
+/// own.
+#[derive(Default)]
+pub struct TextureAtlas {
+    pages: Vec<AtlasPage>,
+    page_size: u32,
+}
+
+impl TextureAtlas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Copies an RGBA image with rows from top to bottom into a page
+    pub fn add_image(&mut self, width: u32, height: u32, pixel_data: &[u8]) -> Result<AtlasRegion, SpriteSheetError> {
+        if width == 0 || height == 0 || pixel_data.len() < (width * height * 4) as usize {
+            return Err(SpriteSheetError::InvalidSpriteDimensions(format!("image of {}x{} has no or too few pixels", width, height)));
+        }
+        let (padded_width, padded_height, padded) = pad_image(width, height, pixel_data);
+
+        let existing = self.pages.iter_mut()
+            .enumerate()
+            .find_map(|(index, page)| Some((index, page.packer.insert(padded_width, padded_height)?)));
+
+        let (index, (x, y)) = match existing {
+            Some(found) => found,
+            None => {
+                let page_size = self.get_page_size();
+                let page = new_page(page_size.max(padded_width), page_size.max(padded_height))?;
+                self.pages.push(page);
+                let index = self.pages.len() - 1;
+                let position = self.pages[index].packer.insert(padded_width, padded_height)
+                    .ok_or(SpriteSheetError::TextureCreationError(format!("{}x{} image doesn't fit a new atlas page", width, height)))?;
+                (index, position)
+            },
+        };
+
+        let page = &self.pages[index];
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, page.texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
+                gl::TEXTURE_2D,
+                0,
+                x as i32,
+                y as i32,
+                padded_width as i32,
+                padded_height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                padded.as_ptr() as *const _,
+            );
+            check_gl_error("Failed to copy image into atlas")?;
+        }
+
+        Ok(AtlasRegion {
+            texture: page.texture,
+            x: x + PADDING,
+            y: y + PADDING,
+            page_width: page.width,
+            page_height: page.height,
+        })
+    }
+
+    fn get_page_size(&mut self) -> u32 {
+        if self.page_size == 0 {
+            let mut max_size = 0;
+            unsafe {
+                gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_size);
+            }
+            self.page_size = if max_size > 0 { DEFAULT_PAGE_SIZE.min(max_size as u32) } else { DEFAULT_PAGE_SIZE };
+        }
+        self.page_size
+    }
+}
+
+fn new_page(width: u32, height: u32) -> Result<AtlasPage, SpriteSheetError> {
+    let mut texture: GLuint = 0;
+    let empty = vec![0u8; (width * height * 4) as usize];
+
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+
+        gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            gl::RGBA as i32,
+            width as i32,
+            height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            empty.as_ptr() as *const _,
+        );
+        if let Err(err) = check_gl_error("Failed to create atlas page") {
+            gl::DeleteTextures(1, &texture);
+            return Err(err);
+        }
+    }
+
+    Ok(AtlasPage {
+        texture,
+        width,
+        height,
+        packer: SkylinePacker::new(width, height),
+    })
+}
+
+/// Surrounds the image with copies of its edge pixels
+fn pad_image(width: u32, height: u32, pixel_data: &[u8]) -> (u32, u32, Vec<u8>) {
+    let (padded_width, padded_height) = (width + 2 * PADDING, height + 2 * PADDING);
+    let mut padded = Vec::with_capacity((padded_width * padded_height * 4) as usize);
+
+    for row in 0..padded_height {
+        let source_row = row.saturating_sub(PADDING).min(height.saturating_sub(1));
+        for col in 0..padded_width {
+            let source_col = col.saturating_sub(PADDING).min(width.saturating_sub(1));
+            let start = ((source_row * width + source_col) * 4) as usize;
+            padded.extend_from_slice(&pixel_data[start..start + 4]);
+        }
+    }
+
+    (padded_width, padded_height, padded)
+}
+
+unsafe fn check_gl_error(context: &str) -> Result<(), SpriteSheetError> {
+    let error = gl::GetError();
+    if error == gl::NO_ERROR {
+        return Ok(());
+    }
+    let info = match error {
+        gl::INVALID_ENUM => "Invalid Enum",
+        gl::INVALID_VALUE => "Invalid Value",
+        gl::INVALID_OPERATION => "Invalid Operation",
+        gl::OUT_OF_MEMORY => "Out of memory",
+        _ => "Unkown error",
+    };
+    Err(SpriteSheetError::TextureCreationError(format!("{}: {}", context, info)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlaps(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn packed_rectangles_fit_and_dont_overlap() {
+        let mut packer = SkylinePacker::new(64, 64);
+        let sizes = [(20, 10), (30, 24), (8, 40), (16, 16), (40, 6), (12, 12), (5, 30), (64, 4)];
+
+        let mut placed: Vec<(u32, u32, u32, u32)> = Vec::new();
+        for (width, height) in sizes {
+            let (x, y) = packer.insert(width, height).unwrap();
+            assert!(x + width <= 64 && y + height <= 64);
+            let rect = (x, y, width, height);
+            assert!(placed.iter().all(|other| !overlaps(rect, *other)), "{:?} overlaps {:?}", rect, placed);
+            placed.push(rect);
+        }
+    }
+
+    #[test]
+    fn rectangles_go_where_they_end_up_lowest() {
+        let mut packer = SkylinePacker::new(32, 32);
+        assert_eq!(packer.insert(16, 8), Some((0, 0)));
+        assert_eq!(packer.insert(16, 4), Some((16, 0)));
+        assert_eq!(packer.insert(16, 4), Some((16, 4)));
+        assert_eq!(packer.insert(32, 4), Some((0, 8)));
+    }
+
+    #[test]
+    fn full_packer_returns_none() {
+        let mut packer = SkylinePacker::new(16, 16);
+        assert_eq!(packer.insert(17, 1), None);
+        assert_eq!(packer.insert(1, 17), None);
+        assert_eq!(packer.insert(16, 12), Some((0, 0)));
+        assert_eq!(packer.insert(8, 8), None);
+        assert_eq!(packer.insert(16, 4), Some((0, 12)));
+        assert_eq!(packer.insert(1, 1), None);
+    }
+
+    #[test]
+    fn padding_copies_the_edge_pixels() {
+        // 2x2 image with the pixels numbered 1 to 4, rows from top to bottom
+        let pixels: Vec<u8> = (1..=4u8).flat_map(|value| [value; 4]).collect();
+        let (width, height, padded) = pad_image(2, 2, &pixels);
+        assert_eq!((width, height), (4, 4));
+
+        let values: Vec<u8> = padded.chunks_exact(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(values, [
+            1, 1, 2, 2,
+            1, 1, 2, 2,
+            3, 3, 4, 4,
+            3, 3, 4, 4,
+        ]);
+    }
+
+    #[test]
+    fn region_uv_skips_the_padding() {
+        let region = AtlasRegion { texture: 0, x: 1, y: 1, page_width: 16, page_height: 8 };
+        assert_eq!(region.get_uv(0, 0, 4, 2), (1.0 / 16.0, 3.0 / 8.0, 5.0 / 16.0, 1.0 / 8.0));
+    }
+}
@@ -0,0 +1,212 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
+pub struct CameraId {
+    id: u32,
+}
+
+impl CameraId {
+    pub(crate) fn new(id: u32) -> Self {
+        Self { id }
+    }
+}
+
+/// A view into the world. World coordinates are the ones sprites are positioned in, one unit
+/// is one pixel at zoom 1 and y points up.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Camera2D {
+    /// World point shown at the center of the viewport
+    position: (f32, f32),
+    zoom: f32,
+    /// Radians, counterclockwise
+    rotation: f32,
+    /// Part of the window the camera draws to as (x, y, width, height) from 0.0 to 1.0, with the
+    /// origin at the bottom left
+    viewport: (f32, f32, f32, f32),
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Camera2D {
+    pub fn new() -> Self {
+        Self {
+            position: (0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: (0.0, 0.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn get_position(&self) -> (f32, f32) {
+        self.position
+    }
+
+    /// Centers the camera on `x`, `y` in world coordinates
+    pub fn set_position(&mut self, x: f32, y: f32) -> &mut Self {
+        self.position = (x, y);
+        self
+    }
+
+    pub fn translate(&mut self, dx: f32, dy: f32) -> &mut Self {
+        self.position.0 += dx;
+        self.position.1 += dy;
+        self
+    }
+
+    pub fn get_zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// 2.0 shows everything twice as big, values at or below 0 are ignored
+    pub fn set_zoom(&mut self, zoom: f32) -> &mut Self {
+        if zoom > 0.0 {
+            self.zoom = zoom;
+        }
+        self
+    }
+
+    pub fn get_rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Rotates the camera counterclockwise, so the world appears to turn clockwise
+    pub fn set_rotation(&mut self, rotation: f32) -> &mut Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn get_viewport(&self) -> (f32, f32, f32, f32) {
+        self.viewport
+    }
+
+    /// Draws to part of the window, e.g. (0.0, 0.0, 0.5, 1.0) for the left half in split-screen
+    pub fn set_viewport(&mut self, x: f32, y: f32, width: f32, height: f32) -> &mut Self {
+        self.viewport = (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0), width.clamp(0.0, 1.0), height.clamp(0.0, 1.0));
+        self
+    }
+
+    /// The transform this camera draws with in a framebuffer of `width` by `height` pixels
+    pub fn get_view(&self, width: i32, height: i32) -> ViewTransform {
+        let (x, y, view_width, view_height) = self.viewport;
+        ViewTransform {
+            center: self.position,
+            zoom: self.zoom,
+            rotation: self.rotation,
+            viewport: (
+                (x * width as f32).round() as i32,
+                (y * height as f32).round() as i32,
+                ((view_width * width as f32).round() as i32).max(1),
+                ((view_height * height as f32).round() as i32).max(1),
+            ),
+        }
+    }
+}
+
+/// A camera resolved against the framebuffer, this is what `Attribute::position` and the other
+/// presets use to place sprites. See `Engine::get_current_view`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ViewTransform {
+    center: (f32, f32),
+    zoom: f32,
+    rotation: f32,
+    /// (x, y, width, height) in framebuffer pixels from the bottom left
+    viewport: (i32, i32, i32, i32),
+}
+
+impl ViewTransform {
+    /// Shows the framebuffer one to one, world coordinates are framebuffer pixels
+    pub fn screen(width: i32, height: i32) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        Self {
+            center: (width as f32 / 2.0, height as f32 / 2.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: (0, 0, width, height),
+        }
+    }
+
+    pub fn get_viewport(&self) -> (i32, i32, i32, i32) {
+        self.viewport
+    }
+
+    pub fn get_zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    pub fn get_rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Width divided by height of the viewport
+    pub fn get_aspect_ratio(&self) -> f32 {
+        self.viewport.2 as f32 / self.viewport.3 as f32
+    }
+
+    /// NDC of a world point inside the viewport
+    pub fn world_to_ndc(&self, x: f32, y: f32) -> [f32; 2] {
+        let (view_x, view_y) = rotate((x - self.center.0, y - self.center.1), -self.rotation);
+        [
+            2.0 * view_x * self.zoom / self.viewport.2 as f32,
+            2.0 * view_y * self.zoom / self.viewport.3 as f32,
+        ]
+    }
+
+    /// Converts a distance in world units to NDC without rotating it. Both axes are scaled by
+    /// the viewport width, the default vertex shader multiplies y by the aspect ratio after
+    /// rotating so sprites don't get skewed.
+    pub fn world_offset_to_ndc(&self, dx: f32, dy: f32) -> [f32; 2] {
+        [
+            2.0 * dx * self.zoom / self.viewport.2 as f32,
+            2.0 * dy * self.zoom / self.viewport.2 as f32,
+        ]
+    }
+
+    /// World point under a framebuffer pixel, with the origin at the bottom left
+    pub fn pixel_to_world(&self, x: f32, y: f32) -> (f32, f32) {
+        let (viewport_x, viewport_y, width, height) = self.viewport;
+        let view = (
+            (x - viewport_x as f32 - width as f32 / 2.0) / self.zoom,
+            (y - viewport_y as f32 - height as f32 / 2.0) / self.zoom,
+        );
+        let (dx, dy) = rotate(view, self.rotation);
+        (self.center.0 + dx, self.center.1 + dy)
+    }
+
+    /// Framebuffer pixel a world point is drawn at, with the origin at the bottom left
+    pub fn world_to_pixel(&self, x: f32, y: f32) -> (f32, f32) {
+        let (viewport_x, viewport_y, width, height) = self.viewport;
+        let (view_x, view_y) = rotate((x - self.center.0, y - self.center.1), -self.rotation);
+        (
+            viewport_x as f32 + width as f32 / 2.0 + view_x * self.zoom,
+            viewport_y as f32 + height as f32 / 2.0 + view_y * self.zoom,
+        )
+    }
+
+    /// (min_x, min_y, max_x, max_y) of the world area the viewport shows
+    pub fn get_visible_bounds(&self) -> (f32, f32, f32, f32) {
+        let (x, y, width, height) = self.viewport;
+        let corners = [
+            self.pixel_to_world(x as f32, y as f32),
+            self.pixel_to_world((x + width) as f32, y as f32),
+            self.pixel_to_world(x as f32, (y + height) as f32),
+            self.pixel_to_world((x + width) as f32, (y + height) as f32),
+        ];
+        corners.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(min_x, min_y, max_x, max_y), &(x, y)| (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)),
+        )
+    }
+
+    pub fn contains_pixel(&self, x: f32, y: f32) -> bool {
+        let (viewport_x, viewport_y, width, height) = self.viewport;
+        x >= viewport_x as f32 && x < (viewport_x + width) as f32
+            && y >= viewport_y as f32 && y < (viewport_y + height) as f32
+    }
+}
+
+fn rotate((x, y): (f32, f32), angle: f32) -> (f32, f32) {
+    let (sin, cos) = angle.sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
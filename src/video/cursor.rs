@@ -0,0 +1,64 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub enum CursorMode {
+    #[default]
+    Normal,
+    /// Invisible while over the window but free to leave it
+    Hidden,
+    /// Hidden and locked to the window for mouse look. The cursor position keeps changing without
+    /// limits, so use `Engine::cursor_delta`. Uses raw mouse motion where the platform supports it.
+    Captured,
+}
+
+impl From<CursorMode> for glfw::CursorMode {
+    fn from(value: CursorMode) -> Self {
+        match value {
+            CursorMode::Normal => glfw::CursorMode::Normal,
+            CursorMode::Hidden => glfw::CursorMode::Hidden,
+            CursorMode::Captured => glfw::CursorMode::Disabled,
+        }
+    }
+}
+
+impl From<glfw::CursorMode> for CursorMode {
+    fn from(value: glfw::CursorMode) -> Self {
+        match value {
+            glfw::CursorMode::Normal => CursorMode::Normal,
+            glfw::CursorMode::Hidden => CursorMode::Hidden,
+            glfw::CursorMode::Disabled => CursorMode::Captured,
+        }
+    }
+}
+
+/// Cursors provided by the operating system
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum CursorShape {
+    Arrow,
+    /// For text fields
+    IBeam,
+    Crosshair,
+    /// For buttons and links
+    Hand,
+    HorizontalResize,
+    VerticalResize,
+}
+
+impl From<CursorShape> for glfw::StandardCursor {
+    fn from(value: CursorShape) -> Self {
+        match value {
+            CursorShape::Arrow => glfw::StandardCursor::Arrow,
+            CursorShape::IBeam => glfw::StandardCursor::IBeam,
+            CursorShape::Crosshair => glfw::StandardCursor::Crosshair,
+            CursorShape::Hand => glfw::StandardCursor::Hand,
+            CursorShape::HorizontalResize => glfw::StandardCursor::HResize,
+            CursorShape::VerticalResize => glfw::StandardCursor::VResize,
+        }
+    }
+}
+
+/// Packs RGBA bytes the way GLFW reads them back for cursors and icons
+pub(crate) fn to_pixel_image(width: u32, height: u32, pixel_data: &[u8]) -> glfw::PixelImage {
+    let pixels = pixel_data.chunks_exact(4)
+        .map(|pixel| u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]))
+        .collect();
+    glfw::PixelImage { width, height, pixels }
+}
@@ -0,0 +1,131 @@
+use crate::video::window::WindowError;
+
+use gl::types::GLuint;
+use image::RgbaImage;
+
+/// Offscreen render target that headless engines draw into instead of the window, so frames
+/// have a fixed size and can be read back without a display showing them.
+pub struct Framebuffer {
+    fbo: GLuint,
+    color: GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Result<Self, WindowError> {
+        if width == 0 || height == 0 {
+            return Err(WindowError::CreationFailed(format!("framebuffer of {}x{} has no pixels", width, height)));
+        }
+
+        let mut fbo: GLuint = 0;
+        let mut color: GLuint = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            gl::GenRenderbuffers(1, &mut color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, color);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::RGBA8, width as i32, height as i32);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, color);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+                gl::DeleteRenderbuffers(1, &color);
+                gl::DeleteFramebuffers(1, &fbo);
+                return Err(WindowError::CreationFailed(format!("framebuffer is incomplete, status 0x{:X}", status)));
+            }
+        }
+
+        Ok(Self {
+            fbo,
+            color,
+            width,
+            height,
+        })
+    }
+
+    pub fn get_size(&self) -> (i32, i32) {
+        (self.width as i32, self.height as i32)
+    }
+
+    /// Makes draws and reads go to this framebuffer
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+    }
+
+    pub fn read_pixels(&self) -> RgbaImage {
+        self.bind();
+        unsafe {
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            read_pixels(self.width, self.height)
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+/// Reads the bound read buffer into an image with rows from top to bottom
+pub(crate) unsafe fn read_pixels(width: u32, height: u32) -> RgbaImage {
+    let row_size = (width * 4) as usize;
+    let mut pixels = vec![0u8; row_size * height as usize];
+
+    gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl::ReadPixels(
+        0,
+        0,
+        width as i32,
+        height as i32,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut _,
+    );
+
+    // OpenGL starts at the bottom row, images at the top one
+    let flipped: Vec<u8> = pixels.chunks_exact(row_size).rev().flatten().copied().collect();
+    RgbaImage::from_raw(width, height, flipped).unwrap_or_else(|| RgbaImage::new(width, height))
+}
+
+/// Number of pixels where any channel differs by more than `tolerance`, for comparing a frame
+/// from `Engine::read_pixels` against a reference image. None if the sizes don't match.
+pub fn count_mismatched_pixels(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Option<usize> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+
+    let mismatched = actual.pixels()
+        .zip(expected.pixels())
+        .filter(|(a, b)| a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > tolerance))
+        .count();
+    Some(mismatched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn mismatches_are_counted_per_pixel_with_tolerance() {
+        let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(0, 0, Rgba([102, 98, 100, 255]));
+        actual.put_pixel(1, 0, Rgba([103, 100, 100, 255]));
+        actual.put_pixel(2, 0, Rgba([0, 0, 0, 0]));
+
+        assert_eq!(count_mismatched_pixels(&actual, &expected, 2), Some(2));
+        assert_eq!(count_mismatched_pixels(&actual, &expected, 0), Some(3));
+        assert_eq!(count_mismatched_pixels(&expected, &expected, 0), Some(0));
+        assert_eq!(count_mismatched_pixels(&actual, &RgbaImage::new(4, 3), 255), None);
+    }
+}
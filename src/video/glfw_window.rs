@@ -1,6 +1,13 @@
 use glfw::{Context, Glfw, PWindow, GlfwReceiver, WindowEvent};
 
 use crate::video::color::Color;
+use crate::video::window::WindowError;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Last error GLFW reported, so failures can be returned instead of panicking in the callback
+type ErrorLog = Rc<RefCell<Option<String>>>;
 
 pub struct GlfwWindow {
     pub glfw: Glfw,
@@ -15,7 +22,7 @@ impl Default for GlfwWindow {
             Color::DARK_GRAY,
             "Window",
             true, true, true, true, true, true, true,
-        )
+        ).expect("Failed to create GLFW window.")
     }
 }
 
@@ -32,9 +39,8 @@ impl GlfwWindow {
         should_poll_cursor_pos: bool,
         should_poll_mouse_buttons: bool,
         should_poll_scroll: bool
-    ) -> Self {
-        use glfw::fail_on_errors;
-        let mut glfw = glfw::init(fail_on_errors!()).unwrap();
+    ) -> Result<Self, WindowError> {
+        let (mut glfw, errors) = init_glfw()?;
 
         if is_resizable {
             glfw.window_hint(glfw::WindowHint::Resizable(true)); 
@@ -54,36 +60,28 @@ impl GlfwWindow {
 
         // Create a windowed mode window and its OpenGL context
         let (mut window, events) = glfw.create_window(width, height, window_name, glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window.");
+            .ok_or_else(|| creation_error(&errors))?;
 
-        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-        unsafe {
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-        }
+        setup_context(&mut window, clear_color);
 
         window.set_framebuffer_size_polling(true);
+        window.set_size_polling(true);
+        window.set_pos_polling(true);
+        window.set_focus_polling(true);
+        window.set_iconify_polling(true);
+        window.set_maximize_polling(true);
+        window.set_close_polling(true);
+        window.set_drag_and_drop_polling(true);
+        window.set_content_scale_polling(true);
         window.set_framebuffer_size_callback(|_, width, height| {
             unsafe {
                 gl::Viewport(0, 0, width, height);
             }
         });
 
-        let (red, green, blue, alpha) = clear_color.to_tuple();
-        let r = red as f32 / 255.0;
-        let g = green as f32 / 255.0;
-        let b = blue as f32 / 255.0;
-        let a = alpha as f32 / 255.0;
-        unsafe {
-            gl::ClearColor(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), a.clamp(0.0, 1.0));
-        }
-
-        // Make the window's context current
-        window.make_current();
-        if should_poll_keys           {window.set_key_polling(true)};
+        if should_poll_keys           {window.set_key_polling(true); window.set_char_polling(true); window.set_store_lock_key_mods(true)};
         if should_poll_scroll         {window.set_scroll_polling(true)};
-        if should_poll_cursor_pos     {window.set_cursor_pos_polling(true)};
+        if should_poll_cursor_pos     {window.set_cursor_pos_polling(true); window.set_cursor_enter_polling(true)};
         if should_poll_mouse_buttons  {window.set_mouse_button_polling(true)};
 
         if show_cursor {
@@ -93,10 +91,53 @@ impl GlfwWindow {
             window.set_cursor_mode(glfw::CursorMode::Hidden);
         }
 
-        GlfwWindow {
+        Ok(GlfwWindow {
             glfw, 
             window, 
             events,
-        }
+        })
+    }
+}
+
+fn init_glfw() -> Result<(Glfw, ErrorLog), WindowError> {
+    let errors: ErrorLog = Rc::new(RefCell::new(None));
+    let last_error = errors.clone();
+    let glfw = glfw::init(move |error, description| {
+        eprintln!("GLFW Error: {:?}: {}", error, description);
+        *last_error.borrow_mut() = Some(description);
+    });
+
+    match glfw {
+        Ok(glfw) => Ok((glfw, errors)),
+        Err(err) => Err(WindowError::CreationFailed(match errors.borrow_mut().take() {
+            Some(description) => format!("{}: {}", err, description),
+            None => err.to_string(),
+        })),
+    }
+}
+
+fn creation_error(errors: &ErrorLog) -> WindowError {
+    let description = errors.borrow_mut().take().unwrap_or("unknown error".to_string());
+    WindowError::CreationFailed(format!("Failed to create GLFW window: {}", description))
+}
+
+/// Makes the window's context current and loads and configures OpenGL for it
+fn setup_context(window: &mut PWindow, clear_color: Color) {
+    window.make_current();
+    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+    setup_gl(clear_color);
+}
+
+/// Blending and clear color every context starts with, windowed or headless
+pub(crate) fn setup_gl(clear_color: Color) {
+    let (red, green, blue, alpha) = clear_color.to_tuple();
+    let r = red as f32 / 255.0;
+    let g = green as f32 / 255.0;
+    let b = blue as f32 / 255.0;
+    let a = alpha as f32 / 255.0;
+    unsafe {
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+        gl::ClearColor(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0), a.clamp(0.0, 1.0));
     }
 }
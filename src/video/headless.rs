@@ -0,0 +1,265 @@
+use crate::video::color::Color;
+use crate::video::window::WindowError;
+
+/// OpenGL 3.3 core context without a window, for headless engines. It's created through EGL on
+/// Mesa's surfaceless platform, so it needs neither a display server nor a GPU. Nothing is
+/// shown, frames go into a `Framebuffer`.
+///
+/// libEGL is loaded at runtime, so it's only needed when an engine is actually built headless.
+pub struct HeadlessContext {
+    #[cfg(unix)]
+    egl: egl::Egl,
+    #[cfg(unix)]
+    display: egl::EGLDisplay,
+    #[cfg(unix)]
+    context: egl::EGLContext,
+    #[cfg(unix)]
+    surface: egl::EGLSurface,
+}
+
+#[cfg(unix)]
+impl HeadlessContext {
+    /// Makes a new context current on this thread and loads OpenGL for it
+    pub fn new(clear_color: Color) -> Result<Self, WindowError> {
+        let egl = egl::Egl::load()?;
+        unsafe {
+            let display = get_display(&egl)?;
+            let (config, context) = match create_context(&egl, display) {
+                Ok(created) => created,
+                Err(err) => {
+                    (egl.terminate)(display);
+                    return Err(err);
+                },
+            };
+
+            // a context without a surface needs EGL_KHR_surfaceless_context, use a pbuffer
+            // when the driver doesn't have it, frames go to a framebuffer either way
+            let mut surface = egl::NO_SURFACE;
+            if (egl.make_current)(display, surface, surface, context) == egl::FALSE {
+                let attributes = [egl::WIDTH, 1, egl::HEIGHT, 1, egl::NONE];
+                surface = (egl.create_pbuffer_surface)(display, config, attributes.as_ptr());
+                if surface == egl::NO_SURFACE || (egl.make_current)(display, surface, surface, context) == egl::FALSE {
+                    let err = egl_error(&egl, "Failed to make the EGL context current");
+                    if surface != egl::NO_SURFACE {
+                        (egl.destroy_surface)(display, surface);
+                    }
+                    (egl.destroy_context)(display, context);
+                    (egl.terminate)(display);
+                    return Err(err);
+                }
+            }
+
+            gl::load_with(|symbol| egl.get_proc_address(symbol));
+            crate::video::glfw_window::setup_gl(clear_color);
+
+            Ok(Self {
+                egl,
+                display,
+                context,
+                surface,
+            })
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl HeadlessContext {
+    pub fn new(_clear_color: Color) -> Result<Self, WindowError> {
+        Err(WindowError::CreationFailed("headless contexts need EGL, which this platform doesn't have".to_string()))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        unsafe {
+            (self.egl.make_current)(self.display, egl::NO_SURFACE, egl::NO_SURFACE, egl::NO_CONTEXT);
+            if self.surface != egl::NO_SURFACE {
+                (self.egl.destroy_surface)(self.display, self.surface);
+            }
+            (self.egl.destroy_context)(self.display, self.context);
+            (self.egl.terminate)(self.display);
+        }
+    }
+}
+
+#[cfg(unix)]
+unsafe fn create_context(egl: &egl::Egl, display: egl::EGLDisplay) -> Result<(egl::EGLConfig, egl::EGLContext), WindowError> {
+    if (egl.initialize)(display, std::ptr::null_mut(), std::ptr::null_mut()) == egl::FALSE {
+        return Err(egl_error(egl, "Failed to initialize EGL"));
+    }
+    if (egl.bind_api)(egl::OPENGL_API) == egl::FALSE {
+        return Err(egl_error(egl, "EGL has no desktop OpenGL"));
+    }
+
+    let config_attributes = [
+        egl::SURFACE_TYPE, egl::PBUFFER_BIT,
+        egl::RENDERABLE_TYPE, egl::OPENGL_BIT,
+        egl::RED_SIZE, 8,
+        egl::GREEN_SIZE, 8,
+        egl::BLUE_SIZE, 8,
+        egl::ALPHA_SIZE, 8,
+        egl::NONE,
+    ];
+    let mut config = std::ptr::null_mut();
+    let mut config_count = 0;
+    if (egl.choose_config)(display, config_attributes.as_ptr(), &mut config, 1, &mut config_count) == egl::FALSE
+        || config_count == 0
+    {
+        return Err(egl_error(egl, "No EGL config for an RGBA8 OpenGL context"));
+    }
+
+    let context_attributes = [
+        egl::CONTEXT_MAJOR_VERSION, 3,
+        egl::CONTEXT_MINOR_VERSION, 3,
+        egl::CONTEXT_OPENGL_PROFILE_MASK, egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+        egl::NONE,
+    ];
+    let context = (egl.create_context)(display, config, egl::NO_CONTEXT, context_attributes.as_ptr());
+    if context == egl::NO_CONTEXT {
+        return Err(egl_error(egl, "Failed to create an OpenGL 3.3 core context"));
+    }
+    Ok((config, context))
+}
+
+/// The surfaceless platform if the EGL library has it, else the default display, which is
+/// surfaceless too on drivers that don't know about windowing systems
+#[cfg(unix)]
+unsafe fn get_display(egl: &egl::Egl) -> Result<egl::EGLDisplay, WindowError> {
+    let get_platform_display = egl.get_proc_address("eglGetPlatformDisplayEXT");
+    if !get_platform_display.is_null() {
+        let get_platform_display: egl::GetPlatformDisplayExt = std::mem::transmute(get_platform_display);
+        let display = get_platform_display(egl::PLATFORM_SURFACELESS_MESA, std::ptr::null_mut(), std::ptr::null());
+        if display != egl::NO_DISPLAY {
+            return Ok(display);
+        }
+    }
+
+    let display = (egl.get_display)(egl::DEFAULT_DISPLAY);
+    if display == egl::NO_DISPLAY {
+        return Err(egl_error(egl, "No EGL display"));
+    }
+    Ok(display)
+}
+
+#[cfg(unix)]
+fn egl_error(egl: &egl::Egl, message: &str) -> WindowError {
+    let code = unsafe { (egl.get_error)() };
+    WindowError::CreationFailed(format!("{}, EGL error 0x{:X}", message, code))
+}
+
+/// The part of EGL 1.4 a headless context needs, loaded with dlopen
+#[cfg(unix)]
+mod egl {
+    use crate::video::window::WindowError;
+
+    use std::ffi::{c_char, c_void, CStr, CString};
+
+    pub type EGLDisplay = *mut c_void;
+    pub type EGLConfig = *mut c_void;
+    pub type EGLContext = *mut c_void;
+    pub type EGLSurface = *mut c_void;
+    pub type EGLBoolean = u32;
+    pub type EGLint = i32;
+
+    pub const FALSE: EGLBoolean = 0;
+    pub const NONE: EGLint = 0x3038;
+    pub const DEFAULT_DISPLAY: *mut c_void = std::ptr::null_mut();
+    pub const NO_DISPLAY: EGLDisplay = std::ptr::null_mut();
+    pub const NO_CONTEXT: EGLContext = std::ptr::null_mut();
+    pub const NO_SURFACE: EGLSurface = std::ptr::null_mut();
+
+    pub const PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+    pub const OPENGL_API: u32 = 0x30A2;
+
+    pub const RED_SIZE: EGLint = 0x3024;
+    pub const GREEN_SIZE: EGLint = 0x3023;
+    pub const BLUE_SIZE: EGLint = 0x3022;
+    pub const ALPHA_SIZE: EGLint = 0x3021;
+    pub const SURFACE_TYPE: EGLint = 0x3033;
+    pub const PBUFFER_BIT: EGLint = 0x0001;
+    pub const RENDERABLE_TYPE: EGLint = 0x3040;
+    pub const OPENGL_BIT: EGLint = 0x0008;
+    pub const WIDTH: EGLint = 0x3057;
+    pub const HEIGHT: EGLint = 0x3056;
+
+    pub const CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+    pub const CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+    pub const CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+    pub const CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x0001;
+
+    pub type GetPlatformDisplayExt = unsafe extern "C" fn(u32, *mut c_void, *const EGLint) -> EGLDisplay;
+
+    pub struct Egl {
+        library: *mut c_void,
+        get_proc_address: unsafe extern "C" fn(*const c_char) -> *const c_void,
+        pub get_error: unsafe extern "C" fn() -> EGLint,
+        pub get_display: unsafe extern "C" fn(*mut c_void) -> EGLDisplay,
+        pub initialize: unsafe extern "C" fn(EGLDisplay, *mut EGLint, *mut EGLint) -> EGLBoolean,
+        pub terminate: unsafe extern "C" fn(EGLDisplay) -> EGLBoolean,
+        pub bind_api: unsafe extern "C" fn(u32) -> EGLBoolean,
+        pub choose_config: unsafe extern "C" fn(EGLDisplay, *const EGLint, *mut EGLConfig, EGLint, *mut EGLint) -> EGLBoolean,
+        pub create_context: unsafe extern "C" fn(EGLDisplay, EGLConfig, EGLContext, *const EGLint) -> EGLContext,
+        pub destroy_context: unsafe extern "C" fn(EGLDisplay, EGLContext) -> EGLBoolean,
+        pub create_pbuffer_surface: unsafe extern "C" fn(EGLDisplay, EGLConfig, *const EGLint) -> EGLSurface,
+        pub destroy_surface: unsafe extern "C" fn(EGLDisplay, EGLSurface) -> EGLBoolean,
+        pub make_current: unsafe extern "C" fn(EGLDisplay, EGLSurface, EGLSurface, EGLContext) -> EGLBoolean,
+    }
+
+    impl Egl {
+        pub fn load() -> Result<Self, WindowError> {
+            let library = ["libEGL.so.1", "libEGL.so"].iter()
+                .map(|name| {
+                    let name = CString::new(*name).unwrap();
+                    unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) }
+                })
+                .find(|library| !library.is_null())
+                .ok_or(WindowError::CreationFailed("Failed to load libEGL".to_string()))?;
+
+            let load = || unsafe {
+                Ok(Self {
+                    get_proc_address: load_symbol(library, c"eglGetProcAddress")?,
+                    get_error: load_symbol(library, c"eglGetError")?,
+                    get_display: load_symbol(library, c"eglGetDisplay")?,
+                    initialize: load_symbol(library, c"eglInitialize")?,
+                    terminate: load_symbol(library, c"eglTerminate")?,
+                    bind_api: load_symbol(library, c"eglBindAPI")?,
+                    choose_config: load_symbol(library, c"eglChooseConfig")?,
+                    create_context: load_symbol(library, c"eglCreateContext")?,
+                    destroy_context: load_symbol(library, c"eglDestroyContext")?,
+                    create_pbuffer_surface: load_symbol(library, c"eglCreatePbufferSurface")?,
+                    destroy_surface: load_symbol(library, c"eglDestroySurface")?,
+                    make_current: load_symbol(library, c"eglMakeCurrent")?,
+                    library,
+                })
+            };
+            load().inspect_err(|_| unsafe {
+                libc::dlclose(library);
+            })
+        }
+
+        pub fn get_proc_address(&self, name: &str) -> *const c_void {
+            match CString::new(name) {
+                Ok(name) => unsafe { (self.get_proc_address)(name.as_ptr()) },
+                Err(_) => std::ptr::null(),
+            }
+        }
+    }
+
+    /// `T` has to be the function pointer type of the symbol
+    unsafe fn load_symbol<T>(library: *mut c_void, name: &CStr) -> Result<T, WindowError> {
+        let symbol = libc::dlsym(library, name.as_ptr());
+        if symbol.is_null() {
+            return Err(WindowError::CreationFailed(format!("libEGL has no {}", name.to_string_lossy())));
+        }
+        Ok(std::mem::transmute_copy(&symbol))
+    }
+
+    impl Drop for Egl {
+        fn drop(&mut self) {
+            unsafe {
+                libc::dlclose(self.library);
+            }
+        }
+    }
+}
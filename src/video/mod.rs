@@ -3,3 +3,11 @@ pub mod sprite;
 pub mod color;
 pub mod shader_manager;
 pub mod glfw_window;
+pub mod window_event;
+pub mod monitor;
+pub mod cursor;
+pub mod camera;
+pub mod atlas;
+pub mod spatial_grid;
+pub mod framebuffer;
+pub mod headless;
@@ -0,0 +1,58 @@
+/// Resolution and refresh rate a monitor can run at
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: u32,
+}
+
+impl From<glfw::VidMode> for VideoMode {
+    fn from(value: glfw::VidMode) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+            refresh_rate: value.refresh_rate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub enum WindowMode {
+    #[default]
+    Windowed,
+    /// A window without decorations covering the whole monitor, switching in and out is fast
+    BorderlessFullscreen,
+    /// Takes over the monitor and changes its resolution to `video_mode`. If the monitor
+    /// doesn't support it exactly the closest mode is used.
+    ExclusiveFullscreen { video_mode: VideoMode },
+}
+
+/// A connected monitor, see `Engine::get_monitors`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MonitorInfo {
+    /// Index to pass to `Engine::set_window_mode`, the primary monitor is always 0
+    pub index: usize,
+    pub name: String,
+    /// Top left corner on the virtual desktop, in screen coordinates
+    pub position: (i32, i32),
+    /// Area not covered by task bars and docks as (x, y, width, height)
+    pub work_area: (i32, i32, i32, i32),
+    pub content_scale: (f32, f32),
+    pub current_video_mode: Option<VideoMode>,
+    /// Every mode usable with `WindowMode::ExclusiveFullscreen`, sorted from smallest to largest
+    pub video_modes: Vec<VideoMode>,
+}
+
+impl MonitorInfo {
+    pub(crate) fn from_glfw(index: usize, monitor: &glfw::Monitor) -> Self {
+        Self {
+            index,
+            name: monitor.get_name().unwrap_or_default(),
+            position: monitor.get_pos(),
+            work_area: monitor.get_workarea(),
+            content_scale: monitor.get_content_scale(),
+            current_video_mode: monitor.get_video_mode().map(VideoMode::from),
+            video_modes: monitor.get_video_modes().into_iter().map(VideoMode::from).collect(),
+        }
+    }
+}
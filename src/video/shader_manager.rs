@@ -12,31 +12,31 @@ pub const DEFAULT_VERTEX_SHADER: &str = r#"
 
 layout (location = 0) in vec2 u_position;
 layout (location = 1) in vec2 tex_coords;
+layout (location = 2) in vec2 sprite_center;
+layout (location = 3) in float rotation;
+layout (location = 4) in vec2 flip;
 
-uniform float u_rotation;
-uniform vec2 u_sprite_center;
 uniform float u_aspect_ratio;
-uniform vec2 u_flip;
 
 out vec2 frag_tex_coords;
 
 void main() {
-    vec2 new_position = u_position - u_sprite_center;
+    vec2 new_position = u_position - sprite_center;
 
     new_position *= mat2(
-        cos(u_rotation), -sin(u_rotation),
-        sin(u_rotation), cos(u_rotation)
+        cos(rotation), -sin(rotation),
+        sin(rotation), cos(rotation)
     );
 
-    if (u_flip.x == 1.0) {
+    if (flip.x == 1.0) {
         new_position.x *= -1.0;
     }
-    if (u_flip.y == 1.0) {
+    if (flip.y == 1.0) {
         new_position.y *= -1.0;
     }
 
     new_position.y *= u_aspect_ratio;
-    new_position += u_sprite_center;
+    new_position += sprite_center;
 
     gl_Position = vec4(new_position, 0.0f, 1.0f);
     frag_tex_coords = tex_coords;
@@ -204,39 +204,39 @@ impl Uniform {
         Self {
             name, 
             callback: |engine: &Engine, _sprite: &Sprite, buffer: &mut Vec<u8>| {
-                let (width, height) = engine.get_window_dimensions();
-                Uniform::push_data_to_result(buffer, &[width as f32 / height as f32]);
+                Uniform::push_data_to_result(buffer, &[engine.get_current_view().get_aspect_ratio()]);
             },
             data_type: UniformDataType::Float,
         }
     }
 
-    /// A preset Uniform that retuns a FloatVec2 of [x, y] position of center of sprite in NDC
+    /// A preset Uniform that retuns a FloatVec2 of [x, y] position of center of sprite in NDC,
+    /// as seen by the camera being drawn
+    #[deprecated(note = "uniforms are set once per batch, so every sprite gets the center of the first one, use Attribute::sprite_center")]
     pub fn sprite_center(name: String) -> Self {
         Self {
             name,
             callback: |engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
-                let (w_width, w_height) = engine.get_window_dimensions();
-                let (s_width, s_height) = (sprite.get_width(), sprite.get_height());
                 let (x, y) = sprite.get_position();
-                let aspect_ratio = w_width as f32 / w_height as f32;
-
-                let data = [
-                    2.0 * (x as f32 + (s_width as f32 / 2.0)) / w_width as f32 - 1.0,
-                    2.0 * ((y as f32 + (s_height as f32 / 2.0)) / aspect_ratio) / w_height as f32 - 1.0
-                ];
+                let data = engine.get_current_view().world_to_ndc(
+                    x as f32 + sprite.get_width() as f32 / 2.0,
+                    y as f32 + sprite.get_height() as f32 / 2.0,
+                );
                 Uniform::push_data_to_result(buffer, &data);
             },
             data_type: UniformDataType::FloatVec2,
         }
     }
 
-    /// A preset Uniform that returns a float representing the rotation of the sprite in radians.
+    /// A preset Uniform that returns a float representing the rotation of the sprite in radians,
+    /// relative to the camera being drawn.
+    #[deprecated(note = "uniforms are set once per batch, so every sprite gets the rotation of the first one, use Attribute::rotation")]
     pub fn rotation(name: String) -> Self {
         Self {
             name,
-            callback: |_engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
-                Uniform::push_data_to_result(buffer, &[sprite.get_rotation()]);
+            callback: |engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
+                let rotation = sprite.get_rotation() - engine.get_current_view().get_rotation();
+                Uniform::push_data_to_result(buffer, &[rotation]);
             },
             data_type: UniformDataType::Float,
         }
@@ -244,6 +244,7 @@ impl Uniform {
 
     /// A preset Uniform that returns a vec2 of float representing [horizontal, vertical], 0.0 for
     /// false, 1.0 for true
+    #[deprecated(note = "uniforms are set once per batch, so every sprite gets the flip of the first one, use Attribute::flip")]
     pub fn flip(name: String) -> Self {
         Self {
             name,
@@ -433,6 +434,21 @@ impl Attribute {
 
     fn write_to_buffer(&self, engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>) {
         (self.callback)(engine, sprite, buffer);
+        // a callback that writes too little would shift every vertex after it
+        buffer.resize(self.vertex_size() * 4, 0);
+    }
+
+    /// Bytes the attribute takes up in one vertex
+    fn vertex_size(&self) -> usize {
+        match self.data_type {
+            AttributeDataType::Float     => size_of::<f32>(),
+            AttributeDataType::FloatVec2 => 2 * size_of::<f32>(),
+            AttributeDataType::FloatVec3 => 3 * size_of::<f32>(),
+            AttributeDataType::FloatVec4 => 4 * size_of::<f32>(),
+            AttributeDataType::Int       => size_of::<i32>(),
+            AttributeDataType::Bool      => size_of::<bool>(),
+            AttributeDataType::UInt      => size_of::<u32>(),
+        }
     }
 
     pub fn write_data_to_buffer<T: Copy>(buffer: &mut Vec<u8>, data: &[T]) {
@@ -443,38 +459,25 @@ impl Attribute {
         }
     }
 
-    /// A preset Attribute that gets the position for each vertex of a sprite in NDC.
+    /// A preset Attribute that gets the position for each vertex of a sprite in NDC, as seen by
+    /// the camera being drawn. The corners aren't rotated, the default vertex shader does that
+    /// around `Attribute::sprite_center`.
     pub fn position(name: String, location: u32) -> Self {
         Self::new(
             name,
             location,
             |engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
-                let (w_width, w_height) = engine.get_window_dimensions();
-                let (s_width, s_height) = (sprite.get_width(), sprite.get_height());
-                let pos = sprite.get_position();
-                let bottom_left = pos;
-                let bottom_right = (pos.0 + s_width as i32, pos.1);
-                let top_left = (pos.0, pos.1 + s_height as i32);
-                let top_right = (pos.0 + s_width as i32, pos.1 + s_height as i32);
-                let aspect_ratio = w_width as f32 / w_height as f32;
+                let view = engine.get_current_view();
+                let (half_width, half_height) = (sprite.get_width() as f32 / 2.0, sprite.get_height() as f32 / 2.0);
+                let (x, y) = sprite.get_position();
+                let center = view.world_to_ndc(x as f32 + half_width, y as f32 + half_height);
+                let offset = view.world_offset_to_ndc(half_width, half_height);
 
                 let data = [
-                    [
-                        2.0 * bottom_left.0 as f32 / w_width as f32 - 1.0,
-                        2.0 * (bottom_left.1 as f32 / aspect_ratio) / w_height as f32 - 1.0
-                    ],
-                    [
-                        2.0 * bottom_right.0 as f32 / w_width as f32 - 1.0,
-                        2.0 * (bottom_right.1 as f32 / aspect_ratio) / w_height as f32 - 1.0
-                    ],
-                    [
-                        2.0 * top_left.0 as f32 / w_width as f32 - 1.0,
-                        2.0 * (top_left.1 as f32 / aspect_ratio) / w_height as f32 - 1.0
-                    ],
-                    [
-                        2.0 * top_right.0 as f32 / w_width as f32 - 1.0,
-                        2.0 * (top_right.1 as f32 / aspect_ratio) / w_height as f32 - 1.0
-                    ],
+                    [center[0] - offset[0], center[1] - offset[1]],
+                    [center[0] + offset[0], center[1] - offset[1]],
+                    [center[0] - offset[0], center[1] + offset[1]],
+                    [center[0] + offset[0], center[1] + offset[1]],
                 ];
                 Attribute::write_data_to_buffer(buffer, &data);
             },
@@ -502,6 +505,57 @@ impl Attribute {
             AttributeDataType::FloatVec2,
         )
     }
+
+    /// A preset Attribute that gets the [x, y] center of the sprite in NDC for each vertex, as
+    /// seen by the camera being drawn.
+    pub fn sprite_center(name: String, location: u32) -> Self {
+        Self::new(
+            name,
+            location,
+            |engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
+                let (x, y) = sprite.get_position();
+                let center = engine.get_current_view().world_to_ndc(
+                    x as f32 + sprite.get_width() as f32 / 2.0,
+                    y as f32 + sprite.get_height() as f32 / 2.0,
+                );
+                Attribute::write_data_to_buffer(buffer, &[center; 4]);
+            },
+            AttributeDataType::FloatVec2,
+        )
+    }
+
+    /// A preset Attribute that gets the rotation of the sprite in radians for each vertex,
+    /// relative to the camera being drawn.
+    pub fn rotation(name: String, location: u32) -> Self {
+        Self::new(
+            name,
+            location,
+            |engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
+                let rotation = sprite.get_rotation() - engine.get_current_view().get_rotation();
+                Attribute::write_data_to_buffer(buffer, &[rotation; 4]);
+            },
+            AttributeDataType::Float,
+        )
+    }
+
+    /// A preset Attribute that gets [horizontal, vertical] for each vertex, 0.0 for false, 1.0
+    /// for true
+    pub fn flip(name: String, location: u32) -> Self {
+        Self::new(
+            name,
+            location,
+            |_engine: &Engine, sprite: &Sprite, buffer: &mut Vec<u8>| {
+                let flip = match sprite.get_flip() {
+                    Flip::None   => [0.0f32, 0.0],
+                    Flip::FlipX  => [1.0, 0.0],
+                    Flip::FlipY  => [0.0, 1.0],
+                    Flip::FlipXY => [1.0, 1.0],
+                };
+                Attribute::write_data_to_buffer(buffer, &[flip; 4]);
+            },
+            AttributeDataType::FloatVec2,
+        )
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -538,9 +592,9 @@ impl VertexArray {
         Self { id }
     }
 
-    /// Sets the attribute to the vao.
+    /// Sets the attribute to the vao, vertices are interleaved and `stride` bytes apart.
     /// Returns the offset for next the attribute.
-    fn set_attribute(&self, attribute: &Attribute, offset: usize) -> usize {
+    fn set_attribute(&self, attribute: &Attribute, offset: usize, stride: usize) -> usize {
         let (len, size, gl_type) = match attribute.data_type {
             AttributeDataType::Float     => (1, size_of::<f32>(), gl::FLOAT),
             AttributeDataType::FloatVec2 => (2, size_of::<f32>(), gl::FLOAT),
@@ -557,11 +611,11 @@ impl VertexArray {
                 len,
                 gl_type,
                 gl::FALSE,
-                0,
+                stride as i32,
                 offset as *const _,
             );
         }
-        (len as usize * size * 4) + offset
+        (len as usize * size) + offset
     }
 
     pub fn bind(&self) {
@@ -666,17 +720,19 @@ impl ShaderProgram {
             let vbo = VertexBuffer::new(gl::ARRAY_BUFFER);
             vbo.bind();
 
-            let ebo = VertexBuffer::new(gl::ELEMENT_ARRAY_BUFFER);
-            ebo.bind();
-
+            let stride: usize = attributes.iter().map(|attribute| attribute.vertex_size()).sum();
             let mut offset: usize = 0;
             let vao = VertexArray::new();
             vao.bind();
+
+            // the element buffer binding is part of the vao, so it has to be bound after it
+            let ebo = VertexBuffer::new(gl::ELEMENT_ARRAY_BUFFER);
+            ebo.bind();
             for attribute in attributes.iter() {
-                offset = vao.set_attribute(attribute, offset);
+                offset = vao.set_attribute(attribute, offset, stride);
             }
 
-            let bytes_per_sprite = offset as usize;
+            let bytes_per_sprite = stride * 4;
             let sprite_capacity: usize = 10_000;
             vbo.bind();
 
@@ -742,10 +798,21 @@ impl ShaderProgram {
 
     pub fn fill_vbo(&self, engine: &Engine, sprites: &Vec<&Sprite>, sprite_size: usize) {
         let mut buffer = Vec::with_capacity(sprite_size as usize * sprites.len());
+        let mut sprite_data: Vec<Vec<u8>> = vec![Vec::new(); self.attributes.len()];
 
         for sprite in sprites {
-            for attribute in self.attributes() {
-                attribute.write_to_buffer(engine, sprite, &mut buffer);
+            for (attribute, data) in self.attributes().iter().zip(sprite_data.iter_mut()) {
+                data.clear();
+                attribute.write_to_buffer(engine, sprite, data);
+            }
+
+            // callbacks write all 4 vertices at once, interleave them so each vertex has its
+            // attributes next to each other
+            for vertex in 0..4 {
+                for (attribute, data) in self.attributes().iter().zip(sprite_data.iter()) {
+                    let size = attribute.vertex_size();
+                    buffer.extend_from_slice(&data[vertex * size..(vertex + 1) * size]);
+                }
             }
         }
 
@@ -0,0 +1,206 @@
+use crate::video::sprite::SpriteId;
+
+use std::collections::{HashMap, HashSet};
+
+/// Sprites spanning more cells than this aren't put in cells and get checked every query
+const MAX_CELLS_PER_SPRITE: i64 = 64;
+
+/// (min_x, min_y, max_x, max_y) in world coordinates
+pub type Bounds = (f32, f32, f32, f32);
+
+struct Entry {
+    bounds: Bounds,
+    /// (min_x, min_y, max_x, max_y) of the cells the sprite is in, None for large sprites
+    cells: Option<(i32, i32, i32, i32)>,
+}
+
+/// Uniform grid of sprite bounds for finding the sprites a camera can see without looking at
+/// every sprite.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<SpriteId>>,
+    entries: HashMap<SpriteId, Entry>,
+    large: HashSet<SpriteId>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            large: HashSet::new(),
+        }
+    }
+
+    /// Adds the sprite or moves it to `bounds` if it's already in the grid
+    pub fn insert(&mut self, id: SpriteId, bounds: Bounds) {
+        let cells = self.get_cell_range(bounds);
+        let cells = (get_cell_count(cells) <= MAX_CELLS_PER_SPRITE).then_some(cells);
+
+        if let Some(entry) = self.entries.get_mut(&id) {
+            if entry.cells == cells {
+                entry.bounds = bounds;
+                return;
+            }
+            self.remove(id);
+        }
+
+        match cells {
+            Some((min_x, min_y, max_x, max_y)) => {
+                for x in min_x..=max_x {
+                    for y in min_y..=max_y {
+                        self.cells.entry((x, y)).or_default().push(id);
+                    }
+                }
+            },
+            None => {
+                self.large.insert(id);
+            },
+        }
+        self.entries.insert(id, Entry { bounds, cells });
+    }
+
+    pub fn remove(&mut self, id: SpriteId) {
+        let Some(entry) = self.entries.remove(&id) else {
+            return;
+        };
+
+        let Some((min_x, min_y, max_x, max_y)) = entry.cells else {
+            self.large.remove(&id);
+            return;
+        };
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|sprite| *sprite != id);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sprites whose bounds overlap `area`, sorted by id so the order is the same every frame
+    pub fn query(&self, area: Bounds) -> Vec<SpriteId> {
+        let mut found = self.find(area);
+        found.sort_unstable();
+        found
+    }
+
+    fn find(&self, area: Bounds) -> Vec<SpriteId> {
+        let range = self.get_cell_range(area);
+
+        // zoomed far out it's cheaper to look at every sprite than at every cell
+        if get_cell_count(range) > self.entries.len() as i64 {
+            return self.entries.iter()
+                .filter(|(_, entry)| overlaps(entry.bounds, area))
+                .map(|(id, _)| *id)
+                .collect();
+        }
+
+        let (min_x, min_y, max_x, max_y) = range;
+        let mut found: HashSet<SpriteId> = HashSet::new();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend(cell.iter().copied());
+                }
+            }
+        }
+        found.extend(self.large.iter().copied());
+
+        found.into_iter()
+            .filter(|id| self.entries.get(id).is_some_and(|entry| overlaps(entry.bounds, area)))
+            .collect()
+    }
+
+    fn get_cell_range(&self, (min_x, min_y, max_x, max_y): Bounds) -> (i32, i32, i32, i32) {
+        (
+            (min_x / self.cell_size).floor() as i32,
+            (min_y / self.cell_size).floor() as i32,
+            (max_x / self.cell_size).floor() as i32,
+            (max_y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+fn get_cell_count((min_x, min_y, max_x, max_y): (i32, i32, i32, i32)) -> i64 {
+    (max_x as i64 - min_x as i64 + 1) * (max_y as i64 - min_y as i64 + 1)
+}
+
+fn overlaps(a: Bounds, b: Bounds) -> bool {
+    a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(values: &[u32]) -> Vec<SpriteId> {
+        values.iter().map(|value| SpriteId::new(*value)).collect()
+    }
+
+    #[test]
+    fn insert_query_and_remove() {
+        let mut grid = SpatialGrid::new(10.0);
+        grid.insert(SpriteId::new(1), (0.0, 0.0, 5.0, 5.0));
+        grid.insert(SpriteId::new(2), (12.0, 12.0, 18.0, 18.0));
+
+        assert_eq!(grid.query((0.0, 0.0, 9.0, 9.0)), ids(&[1]));
+        assert_eq!(grid.query((4.0, 4.0, 14.0, 14.0)), ids(&[1, 2]));
+        assert!(grid.query((30.0, 30.0, 40.0, 40.0)).is_empty());
+
+        grid.remove(SpriteId::new(1));
+        assert_eq!(grid.query((0.0, 0.0, 20.0, 20.0)), ids(&[2]));
+        grid.remove(SpriteId::new(1));
+        assert_eq!(grid.query((0.0, 0.0, 20.0, 20.0)), ids(&[2]));
+    }
+
+    #[test]
+    fn inserting_again_moves_the_sprite() {
+        let mut grid = SpatialGrid::new(10.0);
+        let id = SpriteId::new(1);
+        grid.insert(id, (0.0, 0.0, 5.0, 5.0));
+
+        // into other cells
+        grid.insert(id, (50.0, 50.0, 55.0, 55.0));
+        assert!(grid.query((0.0, 0.0, 9.0, 9.0)).is_empty());
+        assert_eq!(grid.query((45.0, 45.0, 60.0, 60.0)), [id]);
+
+        // within the same cell
+        grid.insert(id, (56.0, 56.0, 58.0, 58.0));
+        assert!(grid.query((50.0, 50.0, 55.0, 55.0)).is_empty());
+        assert_eq!(grid.query((57.0, 57.0, 59.0, 59.0)), [id]);
+    }
+
+    #[test]
+    fn large_sprites_are_found_anywhere_they_overlap() {
+        let mut grid = SpatialGrid::new(10.0);
+        let id = SpriteId::new(1);
+        grid.insert(id, (-500.0, -500.0, 500.0, 500.0));
+        // enough small sprites that the queries below go through the cells
+        for value in 2..8 {
+            grid.insert(SpriteId::new(value), (0.0, 0.0, 5.0, 5.0));
+        }
+
+        assert_eq!(grid.query((300.0, 300.0, 310.0, 310.0)), [id]);
+        assert!(grid.query((600.0, 600.0, 610.0, 610.0)).is_empty());
+
+        grid.remove(id);
+        assert!(grid.query((300.0, 300.0, 310.0, 310.0)).is_empty());
+    }
+
+    #[test]
+    fn results_are_sorted_by_id() {
+        let mut grid = SpatialGrid::new(10.0);
+        for value in [5, 3, 9, 1, 7] {
+            grid.insert(SpriteId::new(value), (0.0, 0.0, 25.0, 25.0));
+        }
+
+        assert_eq!(grid.query((0.0, 0.0, 9.0, 9.0)), ids(&[1, 3, 5, 7, 9]));
+        // an area with more cells than sprites scans every sprite instead
+        assert_eq!(grid.query((-1000.0, -1000.0, 1000.0, 1000.0)), ids(&[1, 3, 5, 7, 9]));
+    }
+}
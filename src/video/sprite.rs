@@ -1,10 +1,14 @@
 use crate::video::shader_manager::ShaderId;
 use crate::video::color::Color;
+use crate::video::atlas::TextureAtlas;
 use crate::utility::file_parser;
 use crate::engine::GetId;
 
 use image::ImageError;
 use gl::types::GLuint;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static NEXT_SPRITE_SHEET_ID: AtomicU32 = AtomicU32::new(0);
 
 #[derive(Debug)]
 pub enum SpriteSheetError {
@@ -39,10 +43,12 @@ pub enum Flip {
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct SpriteSheetId {
-    id: GLuint,
+    id: u32,
 }
 
 pub struct SpriteSheet {
+    id: u32,
+    /// UVs inside the atlas page the sheet was packed into
     sprites_uv: Vec<(f32, f32, f32, f32)>,
     texture_id: u32,
 }
@@ -50,7 +56,7 @@ pub struct SpriteSheet {
 impl GetId for SpriteSheet {
     type Id = SpriteSheetId;
     fn id(&self) -> SpriteSheetId {
-        SpriteSheetId { id: self.texture_id }
+        SpriteSheetId { id: self.id }
     }
 }
 
@@ -59,11 +65,51 @@ impl SpriteSheet {
         self.sprites_uv[index]
     }
 
+    /// The atlas page the sheet is in, sheets on the same page can be drawn together
     pub fn get_texture(&self) -> GLuint {
         self.texture_id
     }
-    
-    pub fn from_image(png_path: &str, sprite_width: u32, sprite_height: u32) -> Result<Self, SpriteSheetError> {
+
+    /// Reads the RGBA pixels of sprite `index` back from the texture, rows from top to bottom.
+    /// Returns (width, height, pixels), None if there is no sprite at `index`.
+    pub fn get_sprite_pixels(&self, index: usize) -> Option<(u32, u32, Vec<u8>)> {
+        let (u_min, v_min, u_max, v_max) = *self.sprites_uv.get(index)?;
+
+        let (mut width, mut height) = (0, 0);
+        let mut pixel_data;
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.texture_id);
+            gl::GetTexLevelParameteriv(gl::TEXTURE_2D, 0, gl::TEXTURE_WIDTH, &mut width);
+            gl::GetTexLevelParameteriv(gl::TEXTURE_2D, 0, gl::TEXTURE_HEIGHT, &mut height);
+
+            pixel_data = vec![0u8; (width * height * 4) as usize];
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::UNSIGNED_BYTE, pixel_data.as_mut_ptr() as *mut _);
+        }
+
+        // the uvs of a sprite have v_min at the bottom, see from_image. Colors point at the
+        // middle of their pixel, so make sure at least one pixel is read.
+        let left = (u_min.min(u_max) * width as f32).floor() as usize;
+        let right = ((u_min.max(u_max) * width as f32).round() as usize).max(left + 1);
+        let top = (v_min.min(v_max) * height as f32).floor() as usize;
+        let bottom = ((v_min.max(v_max) * height as f32).round() as usize).max(top + 1);
+
+        let mut sprite_pixels = Vec::with_capacity((right - left) * (bottom - top) * 4);
+        for row in top..bottom {
+            let start = (row * width as usize + left) * 4;
+            sprite_pixels.extend_from_slice(&pixel_data[start..start + (right - left) * 4]);
+        }
+
+        Some(((right - left) as u32, (bottom - top) as u32, sprite_pixels))
+    }
+
+    /// Loads the image and packs it into `atlas`
+    pub fn from_image(
+        png_path: &str,
+        sprite_width: u32,
+        sprite_height: u32,
+        atlas: &mut TextureAtlas,
+    ) -> Result<Self, SpriteSheetError> {
         let (width, height, pixel_data) = file_parser::get_rbga_from_image(png_path)?;
 
         if width % sprite_width != 0 || height % sprite_height != 0 {
@@ -72,83 +118,52 @@ impl SpriteSheet {
             ));
         }
 
+        let region = atlas.add_image(width, height, &pixel_data)?;
         let mut sprites_uv = Vec::new();
 
+        // rows go from top to bottom, get_uv puts v_min at the bottom of each sprite
         for row in 0..(height / sprite_height) {
             for col in 0..(width / sprite_width) {
-                // we're iterating from top to bottom, so we need to invert the v calculations
-                let u_min = col as f32 * sprite_width as f32 / width as f32;
-                let v_min = (row + 1) as f32 * sprite_height as f32 / height as f32;
-                let u_max = (col + 1) as f32 * sprite_width as f32 / width as f32;
-                let v_max = row as f32 * sprite_height as f32 / height as f32;
-
-                sprites_uv.push((u_min, v_min, u_max, v_max));
+                sprites_uv.push(region.get_uv(col * sprite_width, row * sprite_height, sprite_width, sprite_height));
             }
         }
 
-        let texture_id = get_texture_id(width, height, pixel_data)?;
-
         Ok(SpriteSheet {
+            id: NEXT_SPRITE_SHEET_ID.fetch_add(1, Ordering::Relaxed),
             sprites_uv,
-            texture_id,
+            texture_id: region.texture,
         })
     }
 
-    pub fn from_color(color: Color) -> Result<Self, SpriteSheetError> {
+    /// A single pixel of `color` packed into `atlas`
+    pub fn from_color(color: Color, atlas: &mut TextureAtlas) -> Result<Self, SpriteSheetError> {
         let pixel_data = vec![color.r, color.g, color.b, color.a];
-        let texture_id = get_texture_id(1, 1, pixel_data)?;
+        let region = atlas.add_image(1, 1, &pixel_data)?;
+
+        // sample the middle of the pixel so filtering never reaches a neighbour
+        let u = (region.x as f32 + 0.5) / region.page_width as f32;
+        let v = (region.y as f32 + 0.5) / region.page_height as f32;
 
         Ok(SpriteSheet {
-            sprites_uv: vec![(0.0, 0.0, 1.0, 1.0)],
-            texture_id,
+            id: NEXT_SPRITE_SHEET_ID.fetch_add(1, Ordering::Relaxed),
+            sprites_uv: vec![(u, v, u, v)],
+            texture_id: region.texture,
         })
     }
 }
 
-fn get_texture_id(width: u32, height: u32, pixel_data: Vec<u8>) -> Result<GLuint, SpriteSheetError> {
-    let mut texture_id: GLuint = 0;
-
-    unsafe {
-        gl::GenTextures(1, &mut texture_id);
-        gl::BindTexture(gl::TEXTURE_2D, texture_id);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-
-        gl::TexImage2D(
-            gl::TEXTURE_2D,
-            0,
-            gl::RGBA as i32,
-            width as i32,
-            height as i32,
-            0,
-            gl::RGBA,
-            gl::UNSIGNED_BYTE,
-            pixel_data.as_ptr() as *const _,
-        );
-
-        // TODO improve error message
-        let error = gl::GetError();
-        if error != gl::NO_ERROR {
-            let info = match error {
-                gl::INVALID_ENUM => "Invalid Enum",
-                gl::INVALID_VALUE => "Invalid Value",
-                gl::INVALID_OPERATION => "Invalid Operation",
-                gl::OUT_OF_MEMORY => "Out of memory",
-                _ => "Unkown error",
-            };
-            return Err(SpriteSheetError::TextureCreationError(format!("Failed to create texture: {}", info)));
-        }
-    }
-    Ok(texture_id)
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 pub struct SpriteId {
     id: u32,
 }
 
+impl SpriteId {
+    #[cfg(test)]
+    pub(crate) fn new(id: u32) -> Self {
+        Self { id }
+    }
+}
+
 pub struct Sprite {
     sprite_id: u32,
     x_position: i32,
@@ -161,6 +176,8 @@ pub struct Sprite {
     sprite_sheet: SpriteSheetId,
     sprite_sheet_index: usize,
     shader: ShaderId,
+    /// Set when the bounds change so the spatial index of the window gets updated
+    has_moved: bool,
 }
 
 impl GetId for Sprite {
@@ -193,6 +210,7 @@ impl Sprite {
             rotation: 0.0,
             flip: Flip::None,
             shader,
+            has_moved: true,
         }
     }
 
@@ -219,6 +237,7 @@ impl Sprite {
     pub fn translate(&mut self, dx: i32, dy: i32) -> &mut Self {
         self.x_position += dx;
         self.y_position += dy;
+        self.has_moved = true;
         self
     }
 
@@ -243,28 +262,33 @@ impl Sprite {
 
     pub fn set_height(&mut self, height: u32) -> &mut Self {
         self.height = height as f32;
+        self.has_moved = true;
         self
     }
 
     pub fn set_width(&mut self, width: u32) -> &mut Self {
         self.width = width as f32;
+        self.has_moved = true;
         self
     }
 
     pub fn set_position(&mut self, x: i32, y: i32) -> &mut Self {
         self.x_position = x;
         self.y_position = y;
+        self.has_moved = true;
         self
     }
 
     pub fn set_scale(&mut self, scale_x: f32, scale_y: f32) -> &mut Self {
         self.width *= scale_x;
         self.height *= scale_y;
+        self.has_moved = true;
         self
     }
 
     pub fn set_rotation(&mut self, rotation: f32) -> &mut Self {
         self.rotation = rotation;
+        self.has_moved = true;
         self
     }
 
@@ -284,4 +308,19 @@ impl Sprite {
     pub fn get_width(&self) -> u32 {
         self.width as u32
     }
+
+    /// (min_x, min_y, max_x, max_y) of the box around the rotated sprite
+    pub fn get_bounds(&self) -> (f32, f32, f32, f32) {
+        let (half_width, half_height) = (self.get_width() as f32 / 2.0, self.get_height() as f32 / 2.0);
+        let (center_x, center_y) = (self.x_position as f32 + half_width, self.y_position as f32 + half_height);
+        let (sin, cos) = self.rotation.sin_cos();
+        let extent_x = (half_width * cos).abs() + (half_height * sin).abs();
+        let extent_y = (half_width * sin).abs() + (half_height * cos).abs();
+        (center_x - extent_x, center_y - extent_y, center_x + extent_x, center_y + extent_y)
+    }
+
+    /// True once after the bounds changed
+    pub(crate) fn take_moved(&mut self) -> bool {
+        std::mem::replace(&mut self.has_moved, false)
+    }
 }
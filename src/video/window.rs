@@ -3,20 +3,77 @@ use gl::types::GLuint;
 
 use crate::engine::{GetId, Engine};
 use crate::utility::timer::Timer;
+use crate::utility::file_parser;
 use crate::video::color::Color;
+use crate::video::monitor::{MonitorInfo, WindowMode};
+use crate::video::cursor::{self, CursorMode, CursorShape};
+use crate::video::camera::{Camera2D, CameraId, ViewTransform};
+use crate::video::atlas::TextureAtlas;
+use crate::video::spatial_grid::SpatialGrid;
+use crate::video::framebuffer::{self, Framebuffer};
+use crate::video::headless::HeadlessContext;
 use crate::video::sprite::{Sprite, SpriteId, SpriteSheet, SpriteSheetId, SpriteSheetError};
 use crate::video::shader_manager::{
     ShaderId, VertexShader, FragmentShader, ShaderError, ShaderProgram,
     DEFAULT_FRAGMENT_SHADER, DEFAULT_VERTEX_SHADER, Attribute, Uniform,
 };
 
+use image::{ImageError, RgbaImage};
+
+use std::cell::Cell;
+use std::rc::Rc;
 use std::thread::yield_now;
 use std::collections::{HashMap, BTreeMap};
 
+#[derive(Debug)]
+pub enum WindowError {
+    Image(ImageError),
+    MonitorNotFound(usize),
+    InvalidImage(String),
+    CreationFailed(String),
+}
+
+impl std::fmt::Display for WindowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowError::Image(e) => write!(f, "Image: {}", e),
+            WindowError::MonitorNotFound(index) => write!(f, "MonitorNotFound: no monitor with index {}", index),
+            WindowError::InvalidImage(e) => write!(f, "InvalidImage: {}", e),
+            WindowError::CreationFailed(e) => write!(f, "CreationFailed: {}", e),
+        }
+    }
+}
+
+impl From<ImageError> for WindowError {
+    fn from(value: ImageError) -> Self {
+        WindowError::Image(value)
+    }
+}
+
+/// Counts of the last drawn frame. Sprites are counted once per camera that draws them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FrameStats {
+    pub sprites_drawn: usize,
+    /// Sprites skipped because they were outside the view
+    pub sprites_culled: usize,
+    pub draw_calls: usize,
+}
+
+/// Size of a spatial grid cell in world units
+const GRID_CELL_SIZE: f32 = 256.0;
+
 pub struct WindowManager {
-    window: PWindow,
+    /// None when headless, window settings are ignored then
+    window: Option<PWindow>,
     sprite_sheets: HashMap<SpriteSheetId, SpriteSheet>,
+    atlas: TextureAtlas,
+    /// One single pixel sheet per quad color
+    color_sheets: HashMap<Color, SpriteSheetId>,
     sprites: HashMap<SpriteId, Sprite>,
+    spatial_grid: SpatialGrid,
+    /// Sprites handed out by get_sprite since the last frame, they might have moved
+    touched_sprites: Vec<SpriteId>,
+    frame_stats: FrameStats,
     shaders: HashMap<ShaderId, ShaderProgram>,
     default_fragment: Option<FragmentShader>,
     default_vertex: Option<VertexShader>,
@@ -25,11 +82,53 @@ pub struct WindowManager {
     target_frame_time: f32,
     show_fps: bool,
     last_sprite_id: u32,
+    /// Shared with the close callback of the window
+    is_close_intercepted: Rc<Cell<bool>>,
+    window_mode: WindowMode,
+    /// Position and size as (x, y, width, height) to go back to when leaving fullscreen
+    windowed_rect: (i32, i32, i32, i32),
+    was_decorated: bool,
+    /// Drawn in order of their ids, the whole window is shown one to one when there are none
+    cameras: BTreeMap<CameraId, Camera2D>,
+    last_camera_id: u32,
+    /// The view being drawn, read by attribute and uniform callbacks
+    current_view: Cell<ViewTransform>,
+    /// Frames are drawn here instead of the window when headless
+    framebuffer: Option<Framebuffer>,
+    /// Set by shutdown when there is no window to close
+    is_shut_down: bool,
+    /// Last so that every GL object above is deleted while the context still exists
+    headless_context: Option<HeadlessContext>,
 }
 
 impl WindowManager {
     pub fn new(window: PWindow) -> Self {
+        Self::build(Some(window), None, None)
+    }
+
+    /// Draws into `framebuffer` with a context that has no window
+    pub fn headless(context: HeadlessContext, framebuffer: Framebuffer) -> Self {
+        Self::build(None, Some(framebuffer), Some(context))
+    }
+
+    fn build(
+        mut window: Option<PWindow>,
+        framebuffer: Option<Framebuffer>,
+        headless_context: Option<HeadlessContext>,
+    ) -> Self {
         let mut shaders = HashMap::new();
+
+        let is_close_intercepted = Rc::new(Cell::new(false));
+        let close_flag = is_close_intercepted.clone();
+        // GLFW marks the window as closing before the callback runs, so undo that here and let
+        // the game decide what to do with the CloseRequested event
+        if let Some(window) = window.as_mut() {
+            window.set_close_callback(move |window| {
+                if close_flag.get() {
+                    window.set_should_close(false);
+                }
+            });
+        }
         
         let default_vertex = VertexShader::new(DEFAULT_VERTEX_SHADER);
         let default_fragment = FragmentShader::new(DEFAULT_FRAGMENT_SHADER);
@@ -58,11 +157,11 @@ impl WindowManager {
                 vec![
                     Attribute::position("u_position".to_string(), 0),
                     Attribute::texture_uv_from_sprite_sheet("tex_coords".to_string(), 1),
+                    Attribute::sprite_center("sprite_center".to_string(), 2),
+                    Attribute::rotation("rotation".to_string(), 3),
+                    Attribute::flip("flip".to_string(), 4),
                 ],
                 vec![
-                    Uniform::flip("u_flip".to_string()),
-                    Uniform::rotation("u_rotation".to_string()),
-                    Uniform::sprite_center("u_sprite_center".to_string()),
                     Uniform::texture_from_sprite_sheet("tex_sample".to_string()),
                     Uniform::aspect_ratio("u_aspect_ratio".to_string()),
                 ],
@@ -86,7 +185,12 @@ impl WindowManager {
         Self{
             window,
             sprite_sheets: HashMap::new(),
+            atlas: TextureAtlas::new(),
+            color_sheets: HashMap::new(),
             sprites: HashMap::new(),
+            spatial_grid: SpatialGrid::new(GRID_CELL_SIZE),
+            touched_sprites: Vec::new(),
+            frame_stats: FrameStats::default(),
             shaders,
             default_vertex: vertex,
             default_fragment: fragment,
@@ -95,6 +199,16 @@ impl WindowManager {
             target_frame_time: 0.0,
             show_fps: false,
             last_sprite_id: 0,
+            is_close_intercepted,
+            window_mode: WindowMode::Windowed,
+            windowed_rect: (0, 0, 0, 0),
+            was_decorated: true,
+            cameras: BTreeMap::new(),
+            last_camera_id: 0,
+            current_view: Cell::new(ViewTransform::screen(1, 1)),
+            framebuffer,
+            is_shut_down: false,
+            headless_context,
         }
     }
 
@@ -107,11 +221,134 @@ impl WindowManager {
     }
 
     pub fn get_dimensions(&self) -> (i32, i32) {
-        self.window.get_framebuffer_size()
+        self.get_framebuffer_size()
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.headless_context.is_some()
+    }
+
+    /// Size of what gets drawn to, the offscreen framebuffer when headless
+    fn get_framebuffer_size(&self) -> (i32, i32) {
+        match &self.framebuffer {
+            Some(framebuffer) => framebuffer.get_size(),
+            None => self.window.as_ref().map_or((0, 0), |window| window.get_framebuffer_size()),
+        }
+    }
+
+    /// Size in window coordinates, a headless engine acts like a window the size of its framebuffer
+    fn get_window_size(&self) -> (i32, i32) {
+        match &self.window {
+            Some(window) => window.get_size(),
+            None => self.get_framebuffer_size(),
+        }
+    }
+
+    /// Copies the last drawn frame into an image with rows from top to bottom
+    pub fn read_pixels(&self) -> RgbaImage {
+        if let Some(framebuffer) = &self.framebuffer {
+            return framebuffer.read_pixels();
+        }
+
+        // the back buffer is undefined after swapping, the finished frame is in the front one
+        let (width, height) = self.get_framebuffer_size();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::ReadBuffer(gl::FRONT);
+            let image = framebuffer::read_pixels(width.max(0) as u32, height.max(0) as u32);
+            gl::ReadBuffer(gl::BACK);
+            image
+        }
+    }
+
+    pub fn add_camera(&mut self, camera: Camera2D) -> CameraId {
+        let camera_id = CameraId::new(self.last_camera_id);
+        self.last_camera_id += 1;
+        self.cameras.insert(camera_id, camera);
+        camera_id
+    }
+
+    pub fn get_camera(&mut self, id: CameraId) -> Option<&mut Camera2D> {
+        self.cameras.get_mut(&id)
+    }
+
+    pub fn remove_camera(&mut self, id: CameraId) {
+        self.cameras.remove(&id);
+    }
+
+    /// Views in the order they are drawn
+    pub fn get_views(&self) -> Vec<ViewTransform> {
+        let (width, height) = self.get_framebuffer_size();
+        if self.cameras.is_empty() {
+            return vec![ViewTransform::screen(width, height)];
+        }
+        self.cameras.values().map(|camera| camera.get_view(width, height)).collect()
+    }
+
+    pub fn get_current_view(&self) -> ViewTransform {
+        self.current_view.get()
+    }
+
+    /// Converts a position in window coordinates (origin top left, as reported by the cursor)
+    /// into world coordinates, using the topmost camera under it
+    pub fn screen_to_world(&self, x: f64, y: f64) -> (f32, f32) {
+        let (pixel_x, pixel_y) = self.window_to_pixel(x, y);
+        let views = self.get_views();
+        let view = views.iter().rev()
+            .find(|view| view.contains_pixel(pixel_x, pixel_y))
+            .unwrap_or(&views[0]);
+        view.pixel_to_world(pixel_x, pixel_y)
+    }
+
+    /// Window coordinates a world point is drawn at by the first camera
+    pub fn world_to_screen(&self, x: f32, y: f32) -> (f64, f64) {
+        let (pixel_x, pixel_y) = self.get_views()[0].world_to_pixel(x, y);
+        self.pixel_to_window(pixel_x, pixel_y)
+    }
+
+    pub fn camera_screen_to_world(&self, camera: CameraId, x: f64, y: f64) -> Option<(f32, f32)> {
+        let (width, height) = self.get_framebuffer_size();
+        let (pixel_x, pixel_y) = self.window_to_pixel(x, y);
+        Some(self.cameras.get(&camera)?.get_view(width, height).pixel_to_world(pixel_x, pixel_y))
+    }
+
+    pub fn camera_world_to_screen(&self, camera: CameraId, x: f32, y: f32) -> Option<(f64, f64)> {
+        let (width, height) = self.get_framebuffer_size();
+        let (pixel_x, pixel_y) = self.cameras.get(&camera)?.get_view(width, height).world_to_pixel(x, y);
+        Some(self.pixel_to_window(pixel_x, pixel_y))
+    }
+
+    /// Window coordinates to framebuffer pixels with the origin at the bottom left, they can
+    /// differ on high DPI screens
+    fn window_to_pixel(&self, x: f64, y: f64) -> (f32, f32) {
+        let (window_width, window_height) = self.get_window_size();
+        let (width, height) = self.get_framebuffer_size();
+        if window_width == 0 || window_height == 0 {
+            return (0.0, 0.0);
+        }
+        (
+            x as f32 * width as f32 / window_width as f32,
+            (window_height as f32 - y as f32) * height as f32 / window_height as f32,
+        )
+    }
+
+    fn pixel_to_window(&self, x: f32, y: f32) -> (f64, f64) {
+        let (window_width, window_height) = self.get_window_size();
+        let (width, height) = self.get_framebuffer_size();
+        if width == 0 || height == 0 {
+            return (0.0, 0.0);
+        }
+        (
+            (x * window_width as f32 / width as f32) as f64,
+            (window_height as f32 - y * window_height as f32 / height as f32) as f64,
+        )
     }
 
     pub fn is_running(&self) -> bool {
-        return !self.window.should_close();
+        match &self.window {
+            Some(window) => !window.should_close(),
+            None => !self.is_shut_down,
+        }
     }
 
     pub fn add_sprite_sheet(
@@ -120,7 +357,7 @@ impl WindowManager {
         sprite_width: u32,
         sprite_height: u32
     ) -> Result<SpriteSheetId, SpriteSheetError> {
-        let sprite_sheet = SpriteSheet::from_image(path, sprite_width, sprite_height)?;
+        let sprite_sheet = SpriteSheet::from_image(path, sprite_width, sprite_height, &mut self.atlas)?;
         let sheet_id = sprite_sheet.id();
         self.sprite_sheets.insert(sheet_id, sprite_sheet);
         Ok(sheet_id)
@@ -135,15 +372,39 @@ impl WindowManager {
         height: u32,
         shader: ShaderId
     ) -> Result<SpriteId, SpriteSheetError> {
-        let sprite_sheet = SpriteSheet::from_color(color)?;
-        let sheet_id = sprite_sheet.id();
-        self.sprite_sheets.insert(sheet_id, sprite_sheet);
+        let sheet_id = match self.color_sheets.get(&color) {
+            Some(sheet_id) => *sheet_id,
+            None => {
+                let sprite_sheet = SpriteSheet::from_color(color, &mut self.atlas)?;
+                let sheet_id = sprite_sheet.id();
+                self.sprite_sheets.insert(sheet_id, sprite_sheet);
+                self.color_sheets.insert(color, sheet_id);
+                sheet_id
+            },
+        };
 
         Ok(self.add_sprite(sheet_id, 0, x, y, layer, width, height, shader))
     }
 
     pub fn get_sprite(&mut self, id: SpriteId) -> Option<&mut Sprite> {
-        self.sprites.get_mut(&id)
+        let sprite = self.sprites.get_mut(&id)?;
+        self.touched_sprites.push(id);
+        Some(sprite)
+    }
+
+    pub fn get_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Moves sprites that changed since the last frame in the spatial grid
+    fn update_spatial_grid(&mut self) {
+        for id in self.touched_sprites.drain(..) {
+            if let Some(sprite) = self.sprites.get_mut(&id) {
+                if sprite.take_moved() {
+                    self.spatial_grid.insert(id, sprite.get_bounds());
+                }
+            }
+        }
     }
 
     pub fn add_shader_program(
@@ -185,34 +446,257 @@ impl WindowManager {
         sprite.set_id(self.last_sprite_id);
         self.last_sprite_id += 1;
         let sprite_id = sprite.id();
+        sprite.take_moved();
+        self.spatial_grid.insert(sprite_id, sprite.get_bounds());
         self.sprites.insert(sprite_id, sprite);
         sprite_id
     }
 
     pub fn remove_sprite(&mut self, sprite_id: SpriteId) {
         self.sprites.remove(&sprite_id);
+        self.spatial_grid.remove(sprite_id);
     }
 
     pub fn toggle_border(&mut self) {
-        if self.window.is_decorated() {
-            self.window.set_decorated(false);
-        }
-        else {
-            self.window.set_decorated(true);
+        if let Some(window) = self.window.as_mut() {
+            if window.is_decorated() {
+                window.set_decorated(false);
+            }
+            else {
+                window.set_decorated(true);
+            }
         }
     }
 
+    /// Switches between a window and borderless fullscreen on the monitor the window is on
     pub fn toggle_fullscreen(&mut self) {
-        if !self.window.is_maximized() {
-            self.window.maximize();
-        }
-        else {
-            self.window.restore();
+        let mode = match self.window_mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+        let monitor = self.get_current_monitor();
+        if let Err(err) = self.set_window_mode(mode, monitor) {
+            eprintln!("Error: Failed to toggle fullscreen:\n\t{}", err);
         }
     }
 
+    pub fn get_window_mode(&self) -> WindowMode {
+        self.window_mode
+    }
+
+    /// Empty when headless
+    pub fn get_monitors(&mut self) -> Vec<MonitorInfo> {
+        let Some(window) = self.window.as_mut() else {
+            return Vec::new();
+        };
+        window.glfw.with_connected_monitors(|_, monitors| {
+            monitors.iter()
+                .enumerate()
+                .map(|(index, monitor)| MonitorInfo::from_glfw(index, monitor))
+                .collect()
+        })
+    }
+
+    /// Index of the monitor the center of the window is on, 0 if it's on none of them
+    pub fn get_current_monitor(&mut self) -> usize {
+        let Some(window) = self.window.as_mut() else {
+            return 0;
+        };
+        let (x, y) = window.get_pos();
+        let (width, height) = window.get_size();
+        let center = (x + width / 2, y + height / 2);
+
+        window.glfw.with_connected_monitors(|_, monitors| {
+            monitors.iter()
+                .position(|monitor| contains(get_monitor_rect(monitor), center))
+                .unwrap_or(0)
+        })
+    }
+
+    /// Leaving `WindowMode::Windowed` stores the position, size and border of the window, going
+    /// back to it restores them. If the stored position isn't on `monitor` the window is
+    /// centered on it instead. Headless engines have no monitors to go fullscreen on.
+    pub fn set_window_mode(&mut self, mode: WindowMode, monitor: usize) -> Result<(), WindowError> {
+        let Some(window) = self.window.as_mut() else {
+            return Err(WindowError::MonitorNotFound(monitor));
+        };
+        let mut glfw = window.glfw.clone();
+        glfw.with_connected_monitors(|_, monitors| {
+            let target = monitors.get(monitor).ok_or(WindowError::MonitorNotFound(monitor))?;
+
+            if self.window_mode == WindowMode::Windowed {
+                let (x, y) = window.get_pos();
+                let (width, height) = window.get_size();
+                self.windowed_rect = (x, y, width, height);
+                self.was_decorated = window.is_decorated();
+            }
+
+            match mode {
+                WindowMode::Windowed => {
+                    let (mut x, mut y, width, height) = self.windowed_rect;
+                    if !contains(get_monitor_rect(target), (x + width / 2, y + height / 2)) {
+                        let (area_x, area_y, area_width, area_height) = target.get_workarea();
+                        x = area_x + (area_width - width) / 2;
+                        y = area_y + (area_height - height) / 2;
+                    }
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, width as u32, height as u32, None);
+                    window.set_decorated(self.was_decorated);
+                },
+                WindowMode::BorderlessFullscreen => {
+                    let (x, y, width, height) = get_monitor_rect(target);
+                    window.set_decorated(false);
+                    window.set_monitor(glfw::WindowMode::Windowed, x, y, width as u32, height as u32, None);
+                },
+                WindowMode::ExclusiveFullscreen { video_mode } => {
+                    window.set_monitor(
+                        glfw::WindowMode::FullScreen(target),
+                        0, 0,
+                        video_mode.width, video_mode.height,
+                        Some(video_mode.refresh_rate),
+                    );
+                },
+            }
+
+            self.window_mode = mode;
+            Ok(())
+        })
+    }
+
     pub fn set_window_size(&mut self, width: i32, height: i32) {
-        self.window.set_size(width, height);
+        if let Some(window) = self.window.as_mut() {
+            window.set_size(width, height);
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_title(title);
+        }
+    }
+
+    pub fn set_icon(&mut self, png_path: &str) -> Result<(), WindowError> {
+        let (width, height, pixel_data) = file_parser::get_rbga_from_image(png_path)?;
+        if let Some(window) = self.window.as_mut() {
+            window.set_icon_from_pixels(vec![cursor::to_pixel_image(width, height, &pixel_data)]);
+        }
+        Ok(())
+    }
+
+    pub fn get_cursor_mode(&self) -> CursorMode {
+        self.window.as_ref().map_or(CursorMode::Normal, |window| window.get_cursor_mode().into())
+    }
+
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        let Some(window) = self.window.as_mut() else {
+            return;
+        };
+        window.set_cursor_mode(mode.into());
+        // GLFW reports an error when raw motion is set without support for it
+        if window.glfw.supports_raw_motion() {
+            window.set_raw_mouse_motion(mode == CursorMode::Captured);
+        }
+    }
+
+    pub fn set_cursor_shape(&mut self, shape: CursorShape) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_cursor(Some(glfw::Cursor::standard(shape.into())));
+        }
+    }
+
+    /// `hotspot` is the pixel of the image that points at things, from the top left corner
+    pub fn set_cursor_image(
+        &mut self,
+        sprite_sheet: SpriteSheetId,
+        sprite_index: usize,
+        hotspot: (u32, u32),
+    ) -> Result<(), WindowError> {
+        let sheet = self.sprite_sheets.get(&sprite_sheet)
+            .ok_or(WindowError::InvalidImage(format!("no sprite sheet with id {:?}", sprite_sheet)))?;
+        let (width, height, pixel_data) = sheet.get_sprite_pixels(sprite_index)
+            .ok_or(WindowError::InvalidImage(format!("sprite sheet has no sprite {}", sprite_index)))?;
+        self.set_cursor_pixels(width, height, &pixel_data, hotspot)
+    }
+
+    pub fn set_cursor_image_from_png(&mut self, png_path: &str, hotspot: (u32, u32)) -> Result<(), WindowError> {
+        let (width, height, pixel_data) = file_parser::get_rbga_from_image(png_path)?;
+        self.set_cursor_pixels(width, height, &pixel_data, hotspot)
+    }
+
+    fn set_cursor_pixels(&mut self, width: u32, height: u32, pixel_data: &[u8], hotspot: (u32, u32)) -> Result<(), WindowError> {
+        if width == 0 || height == 0 || hotspot.0 >= width || hotspot.1 >= height {
+            return Err(WindowError::InvalidImage(
+                format!("hotspot {:?} is outside of the {}x{} cursor image", hotspot, width, height)
+            ));
+        }
+        let image = cursor::to_pixel_image(width, height, pixel_data);
+        if let Some(window) = self.window.as_mut() {
+            window.set_cursor(Some(glfw::Cursor::create_from_pixels(image, hotspot.0, hotspot.1)));
+        }
+        Ok(())
+    }
+
+    /// Goes back to the default arrow cursor
+    pub fn reset_cursor(&mut self) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_cursor(None);
+        }
+    }
+
+    /// Position of the top left corner of the content area in screen coordinates
+    pub fn set_position(&mut self, x: i32, y: i32) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_pos(x, y);
+        }
+    }
+
+    pub fn get_position(&self) -> (i32, i32) {
+        self.window.as_ref().map_or((0, 0), |window| window.get_pos())
+    }
+
+    pub fn set_size_limits(&mut self, min: Option<(u32, u32)>, max: Option<(u32, u32)>) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_size_limits(
+                min.map(|(width, _)| width), min.map(|(_, height)| height),
+                max.map(|(width, _)| width), max.map(|(_, height)| height),
+            );
+        }
+    }
+
+    pub fn set_aspect_ratio_lock(&mut self, ratio: Option<(u32, u32)>) {
+        let Some(window) = self.window.as_mut() else {
+            return;
+        };
+        match ratio {
+            Some((numerator, denominator)) => window.set_aspect_ratio(numerator, denominator),
+            // the wrapper takes u32, but GLFW wants its signed DONT_CARE to remove the lock
+            None => window.set_aspect_ratio(glfw::ffi::DONT_CARE as u32, glfw::ffi::DONT_CARE as u32),
+        }
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_opacity(opacity.clamp(0.0, 1.0));
+        }
+    }
+
+    pub fn get_opacity(&self) -> f32 {
+        self.window.as_ref().map_or(1.0, |window| window.get_opacity())
+    }
+
+    pub fn request_attention(&mut self) {
+        if let Some(window) = self.window.as_mut() {
+            window.request_attention();
+        }
+    }
+
+    pub fn get_clipboard_text(&self) -> Option<String> {
+        self.window.as_ref()?.get_clipboard_string()
+    }
+
+    pub fn set_clipboard_text(&mut self, text: &str) {
+        if let Some(window) = self.window.as_mut() {
+            window.set_clipboard_string(text);
+        }
     }
 
     pub fn toggle_show_fps(&mut self) {
@@ -238,8 +722,27 @@ impl WindowManager {
         self.target_frame_time = 1.0 / fps;
     }
 
+    pub fn set_close_intercept(&mut self, should_intercept: bool) {
+        self.is_close_intercepted.set(should_intercept);
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.window.as_ref().is_some_and(|window| window.is_focused())
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.window.as_ref().is_some_and(|window| window.is_iconified())
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        self.window.as_ref().is_some_and(|window| window.is_maximized())
+    }
+
     pub fn shutdown(&mut self) {
-        self.window.set_should_close(true);
+        match self.window.as_mut() {
+            Some(window) => window.set_should_close(true),
+            None => self.is_shut_down = true,
+        }
     }
 
     pub fn swap_buffers(&mut self) {
@@ -254,7 +757,9 @@ impl WindowManager {
         }
 
         self.timer.reset();
-        self.window.swap_buffers();
+        if let Some(window) = self.window.as_mut() {
+            window.swap_buffers();
+        }
     }
 
     pub fn get_uv_from_sprite_sheet(&self, sprite_sheet: SpriteSheetId, index: usize) -> Option<(f32, f32, f32, f32)> {
@@ -262,19 +767,38 @@ impl WindowManager {
     }
 
     pub unsafe fn draw_frame(&mut self, engine: *const Engine) {
-        gl::Clear(gl::COLOR_BUFFER_BIT);
-
-        // Sort sprites by layer and batch by shader to avoid unnecessary binding
-        let mut grouped_sprites: BTreeMap<i32, HashMap<ShaderId, Vec<&Sprite>>> = BTreeMap::new();
-
-        for sprite in self.sprites.values() {
-            grouped_sprites.entry(sprite.get_layer()).or_default()
-                .entry(sprite.get_shader()).or_default().push(sprite);
+        if let Some(framebuffer) = &self.framebuffer {
+            framebuffer.bind();
         }
+        gl::Clear(gl::COLOR_BUFFER_BIT);
 
-        for groups in grouped_sprites.values() {
-            for (shader, group) in groups.iter() {
-                let program = self.shaders.get(&shader).unwrap();
+        self.update_spatial_grid();
+        let mut frame_stats = FrameStats::default();
+
+        // every camera draws the sprites it can see into its own viewport
+        for view in self.get_views() {
+            let (x, y, width, height) = view.get_viewport();
+            gl::Viewport(x, y, width, height);
+            self.current_view.set(view);
+
+            let visible = self.spatial_grid.query(view.get_visible_bounds());
+            frame_stats.sprites_drawn += visible.len();
+            frame_stats.sprites_culled += self.sprites.len() - visible.len();
+
+            // Sort sprites by layer, then by creation so overlapping sprites keep their order
+            let mut sprites: Vec<(&Sprite, GLuint)> = visible.iter()
+                .filter_map(|id| self.sprites.get(id))
+                .map(|sprite| (sprite, self.get_texture_from_sprite_sheet(sprite.get_sprite_sheet()).unwrap_or(0)))
+                .collect();
+            sprites.sort_by_key(|(sprite, _)| (sprite.get_layer(), sprite.id()));
+
+            // Batch runs of sprites sharing a shader and atlas page, splitting only where they change
+            let batches = sprites.chunk_by(|(a, a_texture), (b, b_texture)| {
+                a.get_shader() == b.get_shader() && a_texture == b_texture
+            });
+            for batch in batches {
+                let group: Vec<&Sprite> = batch.iter().map(|(sprite, _)| *sprite).collect();
+                let program = self.shaders.get(&group[0].get_shader()).unwrap();
                 program.apply();
                 program.apply_uniforms(&*engine, group[0]);
 
@@ -283,8 +807,26 @@ impl WindowManager {
 
 
                 gl::DrawElements(gl::TRIANGLES, (group.len() * 6) as i32, gl::UNSIGNED_INT, std::ptr::null());
+                frame_stats.draw_calls += 1;
             }
         }
+
+        let (width, height) = self.get_framebuffer_size();
+        gl::Viewport(0, 0, width, height);
+        self.frame_stats = frame_stats;
         self.swap_buffers();
     }
 }
+
+/// Area of the virtual desktop the monitor shows as (x, y, width, height)
+fn get_monitor_rect(monitor: &glfw::Monitor) -> (i32, i32, i32, i32) {
+    let (x, y) = monitor.get_pos();
+    match monitor.get_video_mode() {
+        Some(mode) => (x, y, mode.width as i32, mode.height as i32),
+        None => monitor.get_workarea(),
+    }
+}
+
+fn contains((x, y, width, height): (i32, i32, i32, i32), (point_x, point_y): (i32, i32)) -> bool {
+    point_x >= x && point_x < x + width && point_y >= y && point_y < y + height
+}
@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+/// Something that happened to the window, see `Engine::drain_window_events`.
+#[derive(Clone, PartialEq, Debug)]
+pub enum WindowEvent {
+    /// The window was resized, in screen coordinates
+    Resized { width: i32, height: i32 },
+    /// The drawable area changed size in pixels, differs from `Resized` on HiDPI screens
+    FramebufferResized { width: i32, height: i32 },
+    /// The top left corner of the content area moved, in screen coordinates
+    Moved { x: i32, y: i32 },
+    FocusGained,
+    /// Usually a good moment to pause the game
+    FocusLost,
+    Minimized,
+    Maximized,
+    /// Back to a normal window from being minimized or maximized
+    Restored,
+    /// The user tried to close the window. Unless `Engine::set_close_intercept` is enabled the
+    /// window closes and `Engine::is_running` returns false.
+    CloseRequested,
+    /// Files or folders dropped onto the window
+    FilesDropped(Vec<PathBuf>),
+    /// The monitor scale changed, e.g. the window moved to a screen with a different DPI
+    ContentScaleChanged { x: f32, y: f32 },
+}
+
+impl WindowEvent {
+    pub(crate) fn from_glfw(event: &glfw::WindowEvent) -> Option<WindowEvent> {
+        let event = match event {
+            glfw::WindowEvent::Size(width, height) => WindowEvent::Resized { width: *width, height: *height },
+            glfw::WindowEvent::FramebufferSize(width, height) => WindowEvent::FramebufferResized { width: *width, height: *height },
+            glfw::WindowEvent::Pos(x, y) => WindowEvent::Moved { x: *x, y: *y },
+            glfw::WindowEvent::Focus(true) => WindowEvent::FocusGained,
+            glfw::WindowEvent::Focus(false) => WindowEvent::FocusLost,
+            glfw::WindowEvent::Iconify(true) => WindowEvent::Minimized,
+            glfw::WindowEvent::Maximize(true) => WindowEvent::Maximized,
+            glfw::WindowEvent::Iconify(false) | glfw::WindowEvent::Maximize(false) => WindowEvent::Restored,
+            glfw::WindowEvent::Close => WindowEvent::CloseRequested,
+            glfw::WindowEvent::FileDrop(paths) => WindowEvent::FilesDropped(paths.clone()),
+            glfw::WindowEvent::ContentScale(x, y) => WindowEvent::ContentScaleChanged { x: *x, y: *y },
+            _ => return None,
+        };
+        Some(event)
+    }
+}
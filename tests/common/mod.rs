@@ -0,0 +1,34 @@
+use redgir::{count_mismatched_pixels, Color, Engine};
+
+use image::RgbaImage;
+
+/// A headless engine with a black background, None when no GL context can be created. Set
+/// `REDGIR_REQUIRE_GL=1` (as CI does) to fail instead of skipping the render test.
+pub fn headless_engine(width: u32, height: u32) -> Option<Engine> {
+    match Engine::new().headless(width, height).set_clear_color(Color::BLACK).try_init() {
+        Ok(engine) => Some(engine),
+        Err(err) if std::env::var_os("REDGIR_REQUIRE_GL").is_some() => {
+            panic!("REDGIR_REQUIRE_GL is set but no GL context could be created: {}", err)
+        },
+        Err(err) => {
+            eprintln!("skipping render test, set REDGIR_REQUIRE_GL to fail instead: {}", err);
+            None
+        },
+    }
+}
+
+/// Compares a frame against `tests/reference/<name>.png`, allowing a few pixels along the
+/// edges of rotated sprites to round the other way. Run with `REDGIR_UPDATE_REFERENCE=1` to
+/// overwrite the reference with the frame instead.
+pub fn assert_matches_reference(actual: &RgbaImage, name: &str, max_mismatched: usize) {
+    let path = format!("{}/tests/reference/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("REDGIR_UPDATE_REFERENCE").is_some() {
+        actual.save(&path).expect("Failed to write reference image");
+        return;
+    }
+
+    let expected = image::open(&path).expect("Failed to open reference image").to_rgba8();
+    let mismatched = count_mismatched_pixels(actual, &expected, 2)
+        .unwrap_or_else(|| panic!("{} is {:?}, the frame is {:?}", path, expected.dimensions(), actual.dimensions()));
+    assert!(mismatched <= max_mismatched, "{} pixels differ from {}", mismatched, path);
+}
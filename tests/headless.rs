@@ -0,0 +1,22 @@
+mod common;
+
+use redgir::Color;
+
+/// Overlapping quads on three layers, added out of layer order
+#[test]
+fn layered_quads_match_reference() {
+    let Some(mut engine) = common::headless_engine(120, 90) else {
+        return;
+    };
+    assert!(engine.is_headless());
+    let shader = engine.default_shader().unwrap();
+
+    engine.add_quad(Color::WHITE, 10, 10, 0, 60, 40, shader).unwrap();
+    engine.add_quad(Color::RED, 40, 30, 2, 50, 50, shader).unwrap();
+    engine.add_quad(Color::BLUE, 60, 20, 1, 40, 30, shader).unwrap();
+    engine.draw_frame();
+
+    let frame = engine.read_pixels();
+    assert_eq!(frame.dimensions(), (120, 90));
+    common::assert_matches_reference(&frame, "layered_quads", 0);
+}
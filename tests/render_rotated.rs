@@ -0,0 +1,27 @@
+mod common;
+
+use redgir::Color;
+
+/// Sprites in one batch used to share the rotation of the first one, so every sprite here gets
+/// its own rotation while still being drawn with a single draw call
+#[test]
+fn rotated_sprites_in_one_batch() {
+    let Some(mut engine) = common::headless_engine(160, 120) else {
+        return;
+    };
+    let shader = engine.default_shader().unwrap();
+
+    let sprites = [
+        (Color::RED, 15, 0.3),
+        (Color::GREEN, 60, 1.0),
+        (Color::BLUE, 105, -0.6),
+    ];
+    for (color, x, rotation) in sprites {
+        let id = engine.add_quad(color, x, 52, 0, 40, 16, shader).unwrap();
+        engine.get_sprite(id).unwrap().set_rotation(rotation);
+    }
+
+    engine.draw_frame();
+    assert_eq!(engine.get_frame_stats().draw_calls, 1);
+    common::assert_matches_reference(&engine.read_pixels(), "rotated_sprites", 16);
+}